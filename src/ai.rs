@@ -0,0 +1,432 @@
+//! Computer player: a depth-limited expectimax search over the moves of the
+//! player and the tiles which may spawn after them.
+//!
+//! Positions at the bottom of the search are scored by a heuristic, a
+//! weighted sum of features of the board measured on the exponents of
+//! the tiles, which is what the weights of `Heuristic` are relative to.
+//!
+//! 4x4 games are searched on bitboards, other sizes on a slower grid of
+//! any size. On the grid, tiles spawning on boards with many empty cells
+//! are only tried on some of them, evenly spread, so that large boards are
+//! searched about as fast as the standard one.
+
+use bitboard::{ self, Bitboard };
+use game::{ self, Direction, Game, Rules };
+
+/// Score of a position where no move is left.
+static LOST: f64 = -1e9;
+/// Most empty cells tried for every tile which may spawn on the grid.
+static MAX_SPAWN_CELLS: usize = 6;
+
+/// Weights of the features which make a position look promising.
+#[derive(Clone, Debug, PartialEq, RustcEncodable, RustcDecodable)]
+pub struct Heuristic {
+    /// Per empty cell.
+    pub empty_cells: f64,
+    /// Per step of exponent going against the main trend of every row and
+    /// column, as a penalty.
+    pub monotonicity: f64,
+    /// Per step of exponent between neighbouring tiles, as a penalty.
+    pub smoothness: f64,
+    /// Per step of exponent of the highest tile, when it sits in a corner.
+    pub max_in_corner: f64,
+}
+
+impl Default for Heuristic {
+    fn default() -> Heuristic {
+        Heuristic {
+            empty_cells: 2.7,
+            monotonicity: 1.0,
+            smoothness: 0.1,
+            max_in_corner: 1.0,
+        }
+    }
+}
+
+impl Heuristic {
+    /// Value of a `width` by `height` board of exponents, row after row.
+    fn evaluate(&self, width: usize, height: usize, cells: &[u8]) -> f64 {
+        let empty = cells.iter().filter(|&&exponent| exponent == 0).count() as f64;
+
+        let mut against_trend = 0.0;
+        let mut roughness = 0.0;
+        // every row and column as its first cell, the step to the next one
+        // and its length
+        let rows = (0..height).map(|y| (y * width, 1, width));
+        let columns = (0..width).map(|x| (x, width, height));
+        for (first, step, len) in rows.chain(columns) {
+            let (mut up, mut down) = (0.0, 0.0);
+            for k in 1..len {
+                let a = f64::from(cells[first + (k - 1) * step]);
+                let b = f64::from(cells[first + k * step]);
+                if a < b {
+                    up += b - a;
+                } else {
+                    down += a - b;
+                }
+                if a > 0.0 && b > 0.0 {
+                    roughness += (a - b).abs();
+                }
+            }
+            against_trend += if up < down { up } else { down };
+        }
+
+        let max = cells.iter().cloned().max().unwrap_or(0);
+        let last = cells.len() - 1;
+        let corners = [0, width - 1, last + 1 - width, last];
+        let corner = if corners.iter().any(|&i| cells[i] == max) {
+            f64::from(max)
+        } else {
+            0.0
+        };
+
+        self.empty_cells * empty
+            - self.monotonicity * against_trend
+            - self.smoothness * roughness
+            + self.max_in_corner * corner
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, RustcEncodable, RustcDecodable)]
+pub struct Ai {
+    /// Number of moves of the player searched ahead.
+    pub depth: u32,
+    pub heuristic: Heuristic,
+}
+
+impl Default for Ai {
+    fn default() -> Ai {
+        Ai {
+            depth: 2,
+            heuristic: Heuristic::default(),
+        }
+    }
+}
+
+impl Ai {
+    /// Direction with the best expected outcome, `None` if no move is left.
+    pub fn best_move(&self, game: &Game) -> Option<Direction> {
+        Ai::best_of(&self.evaluate_moves(game))
+    }
+
+    /// Direction with the highest evaluation among the moves evaluated by
+    /// `evaluate_moves`.
+    pub fn best_of(evaluations: &[(Direction, Option<f64>)]) -> Option<Direction> {
+        let mut best: Option<(Direction, f64)> = None;
+
+        for &(direction, value) in evaluations.iter() {
+            if let Some(value) = value {
+                let better = match best {
+                    Some((_, best_value)) => value > best_value,
+                    None => true,
+                };
+                if better {
+                    best = Some((direction, value));
+                }
+            }
+        }
+
+        best.map(|(direction, _)| direction)
+    }
+
+    /// Expected outcome of every direction, `None` for the ones which
+    /// aren't among the legal moves of `game`.
+    pub fn evaluate_moves(&self, game: &Game) -> Vec<(Direction, Option<f64>)> {
+        let legal = game.legal_moves();
+        match Bitboard::from_game(game) {
+            Some(board) if fits_bitboard(game.rules()) => self.evaluate_from(&board, game.rules(), &legal, None),
+            _ => self.evaluate_from(&Grid::from_game(game), game.rules(), &legal, Some(MAX_SPAWN_CELLS)),
+        }
+    }
+
+    fn evaluate_from<S: SearchState>(&self, state: &S, rules: &Rules, legal: &[Direction],
+                                     max_spawn_cells: Option<usize>) -> Vec<(Direction, Option<f64>)> {
+        let search = Search {
+            heuristic: &self.heuristic,
+            rules,
+            max_spawn_cells,
+            total_weight: rules.spawn_table.iter().map(|&(_, weight)| f64::from(weight)).sum(),
+        };
+
+        Direction::all().iter().map(|&direction| {
+            let value = if legal.contains(&direction) {
+                state.slide(direction).map(|next| {
+                    search.chance(&next, self.depth.max(1) - 1, rules.spawn_per_move)
+                })
+            } else {
+                None
+            };
+            (direction, value)
+        }).collect()
+    }
+}
+
+/// Whether every tile which may spawn fits in a bitboard.
+fn fits_bitboard(rules: &Rules) -> bool {
+    rules.spawn_table.iter().all(|&(score, _)| {
+        score >= 2 && score.count_ones() == 1 && game::exponent(score) < bitboard::MAX_EXPONENT
+    })
+}
+
+/// A board as the search sees it.
+trait SearchState: Sized {
+    /// The board after sliding towards `direction`, `None` if nothing
+    /// moves.
+    fn slide(&self, direction: Direction) -> Option<Self>;
+    /// Indices of the empty cells.
+    fn empty(&self) -> Vec<usize>;
+    /// The board with a tile of `score` spawned in the empty cell `i`.
+    fn spawn(&self, i: usize, score: i32) -> Self;
+    fn evaluate(&self, heuristic: &Heuristic) -> f64;
+}
+
+struct Search<'a> {
+    heuristic: &'a Heuristic,
+    rules: &'a Rules,
+    /// Most empty cells tried for every tile which may spawn, all of them
+    /// if `None`.
+    max_spawn_cells: Option<usize>,
+    total_weight: f64,
+}
+
+impl<'a> Search<'a> {
+    /// Best value the player can get from `state` with `depth` moves left.
+    fn max<S: SearchState>(&self, state: &S, depth: u32) -> f64 {
+        if depth == 0 {
+            return state.evaluate(self.heuristic);
+        }
+
+        let mut best = LOST;
+        for &direction in Direction::all().iter() {
+            if let Some(next) = state.slide(direction) {
+                let value = self.chance(&next, depth - 1, self.rules.spawn_per_move);
+                if value > best {
+                    best = value;
+                }
+            }
+        }
+        best
+    }
+
+    /// Expected value of `state` over the `spawns` tiles about to spawn.
+    fn chance<S: SearchState>(&self, state: &S, depth: u32, spawns: u32) -> f64 {
+        let empty = state.empty();
+
+        if spawns == 0 || empty.is_empty() {
+            return self.max(state, depth);
+        }
+
+        // the empty cells are as likely as each other, so a few spread over
+        // the board stand for all of them
+        let cells: Vec<usize> = match self.max_spawn_cells {
+            Some(max) if empty.len() > max => (0..max).map(|k| empty[k * empty.len() / max]).collect(),
+            _ => empty,
+        };
+
+        let mut expected = 0.0;
+        for &i in cells.iter() {
+            for &(score, weight) in self.rules.spawn_table.iter() {
+                if weight == 0 {
+                    continue;
+                }
+
+                let next = state.spawn(i, score);
+                let probability = f64::from(weight) / self.total_weight / cells.len() as f64;
+                expected += probability * self.chance(&next, depth, spawns - 1);
+            }
+        }
+        expected
+    }
+}
+
+/// The board as the search sees it: the score of every tile, row after
+/// row, 0 for empty cells. Scores rather than exponents keep tiles apart
+/// when the spawn table has some which aren't powers of two.
+#[derive(Clone, Debug, PartialEq)]
+struct Grid {
+    width: usize,
+    height: usize,
+    cells: Vec<i32>,
+}
+
+impl Grid {
+    fn from_game(game: &Game) -> Grid {
+        let (width, height) = (game.width() as usize, game.height() as usize);
+        let mut cells = vec![0; width * height];
+        for tile in game.tiles() {
+            cells[tile.tile_y as usize * width + tile.tile_x as usize] = tile.score;
+        }
+
+        Grid {
+            width,
+            height,
+            cells,
+        }
+    }
+
+    fn rows(&self) -> Vec<Vec<usize>> {
+        (0..self.height).map(|y| (0..self.width).map(|x| y * self.width + x).collect()).collect()
+    }
+
+    fn columns(&self) -> Vec<Vec<usize>> {
+        (0..self.width).map(|x| (0..self.height).map(|y| y * self.width + x).collect()).collect()
+    }
+
+    /// Cells of every line, each starting from the edge tiles slide
+    /// towards.
+    fn lines(&self, direction: Direction) -> Vec<Vec<usize>> {
+        let mut lines = match direction {
+            Direction::Left | Direction::Right => self.rows(),
+            Direction::Up | Direction::Down => self.columns(),
+        };
+
+        if direction == Direction::Right || direction == Direction::Down {
+            for line in lines.iter_mut() {
+                line.reverse();
+            }
+        }
+        lines
+    }
+
+    /// The grid after sliding towards `direction`, `None` if nothing moves.
+    /// Equal tiles merge the same way as in `Game`, each tile at most once.
+    fn slide(&self, direction: Direction) -> Option<Grid> {
+        let mut next = self.clone();
+
+        for line in self.lines(direction) {
+            let mut packed: Vec<i32> = Vec::with_capacity(line.len());
+            let mut merged = false;
+
+            for &i in line.iter() {
+                let score = self.cells[i];
+                if score == 0 {
+                    continue;
+                }
+
+                if !merged && packed.last().is_some_and(|&last| game::can_merge(last, score)) {
+                    *packed.last_mut().unwrap() += score;
+                    merged = true;
+                } else {
+                    packed.push(score);
+                    merged = false;
+                }
+            }
+
+            for (k, &i) in line.iter().enumerate() {
+                next.cells[i] = packed.get(k).cloned().unwrap_or(0);
+            }
+        }
+
+        if next == *self {
+            None
+        } else {
+            Some(next)
+        }
+    }
+}
+
+impl SearchState for Grid {
+    fn slide(&self, direction: Direction) -> Option<Grid> {
+        Grid::slide(self, direction)
+    }
+
+    fn empty(&self) -> Vec<usize> {
+        (0..self.cells.len()).filter(|&i| self.cells[i] == 0).collect()
+    }
+
+    fn spawn(&self, i: usize, score: i32) -> Grid {
+        let mut next = self.clone();
+        next.cells[i] = score;
+        next
+    }
+
+    fn evaluate(&self, heuristic: &Heuristic) -> f64 {
+        let exponents: Vec<u8> = self.cells.iter().map(|&score| game::exponent(score)).collect();
+        heuristic.evaluate(self.width, self.height, &exponents)
+    }
+}
+
+impl SearchState for Bitboard {
+    fn slide(&self, direction: Direction) -> Option<Bitboard> {
+        let (next, _) = Bitboard::slide(*self, direction);
+        if next == *self {
+            None
+        } else {
+            Some(next)
+        }
+    }
+
+    fn empty(&self) -> Vec<usize> {
+        let cells = self.cells();
+        (0..cells.len()).filter(|&i| cells[i] == 0).collect()
+    }
+
+    fn spawn(&self, i: usize, score: i32) -> Bitboard {
+        self.with(i as i32 % 4, i as i32 / 4, game::exponent(score))
+    }
+
+    fn evaluate(&self, heuristic: &Heuristic) -> f64 {
+        heuristic.evaluate(4, 4, &self.cells())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use game::{ Game, Rules, Status };
+    use super::*;
+
+    /// Plays `rules` with the computer player, checking every move it picks
+    /// is legal, until none is left.
+    fn play(rules: Rules, seed: u64) -> Game {
+        let ai = Ai {
+            depth: 1,
+            ..Ai::default()
+        };
+        let mut game = Game::new(rules, seed);
+
+        for _ in 0..10000 {
+            if game.status() == Status::Won {
+                game.keep_playing();
+            }
+            match ai.best_move(&game) {
+                Some(direction) => {
+                    assert!(game.legal_moves().contains(&direction), "{:?} isn't legal", direction);
+                    game.move_tiles(direction);
+                },
+                None => break,
+            }
+        }
+
+        assert!(game.legal_moves().is_empty(), "the ai gave up with moves left");
+        game
+    }
+
+    #[test]
+    fn moves_on_bitboards() {
+        let game = play(Rules::default(), 0);
+        assert!(game.score() > 0);
+    }
+
+    #[test]
+    fn moves_on_grids() {
+        let rules = Rules {
+            width: 3,
+            height: 3,
+            ..Rules::default()
+        };
+        play(rules, 0);
+    }
+
+    #[test]
+    fn keeps_tiles_which_arent_powers_of_two_apart() {
+        let rules = Rules {
+            width: 3,
+            height: 3,
+            spawn_table: vec![(2, 1), (3, 1)],
+            ..Rules::default()
+        };
+        for seed in 0..4 {
+            play(rules.clone(), seed);
+        }
+    }
+}
@@ -0,0 +1,121 @@
+//! Animations as spans of time with an easing curve, so that how far along
+//! they are only depends on the time and not on the frame rate.
+
+use std::str::FromStr;
+
+/// How an animation progresses over its span of time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Easing {
+    Linear,
+    /// Fast at first, slowing down towards the end.
+    EaseOutCubic,
+    /// Overshoots the end a little before settling on it.
+    Back,
+}
+
+impl Easing {
+    /// Name used in `settings.json`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Easing::Linear => "linear",
+            Easing::EaseOutCubic => "ease_out_cubic",
+            Easing::Back => "back",
+        }
+    }
+
+    /// Progress at the fraction `t` of the time, from 0 at the start to 1
+    /// at the end. `Back` goes past 1 on the way.
+    pub fn apply(self, t: f64) -> f64 {
+        match self {
+            Easing::Linear => t,
+            Easing::EaseOutCubic => 1.0 - (1.0 - t).powi(3),
+            Easing::Back => {
+                let overshoot = 1.70158;
+                let t = t - 1.0;
+                1.0 + (overshoot + 1.0) * t.powi(3) + overshoot * t.powi(2)
+            },
+        }
+    }
+}
+
+impl FromStr for Easing {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Easing, String> {
+        match s {
+            "linear" => Ok(Easing::Linear),
+            "ease_out_cubic" => Ok(Easing::EaseOutCubic),
+            "back" => Ok(Easing::Back),
+            _ => Err(format!("unknown easing {}", s)),
+        }
+    }
+}
+
+/// An animation running from `start` to `end`, in seconds of some clock.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Animation {
+    pub start: f64,
+    pub end: f64,
+    pub easing: Easing,
+}
+
+impl Animation {
+    pub fn new(start: f64, duration: f64, easing: Easing) -> Animation {
+        Animation {
+            start,
+            end: start + duration.max(0.0),
+            easing,
+        }
+    }
+
+    /// Eased progress at `now`, 0 before the start and 1 from the end on.
+    pub fn progress(&self, now: f64) -> f64 {
+        if now >= self.end {
+            1.0
+        } else if now <= self.start {
+            0.0
+        } else {
+            self.easing.apply((now - self.start) / (self.end - self.start))
+        }
+    }
+
+    pub fn is_finished(&self, now: f64) -> bool {
+        now >= self.end
+    }
+
+    /// Value between `from` and `to` at `now`.
+    pub fn interpolate(&self, from: f64, to: f64, now: f64) -> f64 {
+        from + self.progress(now) * (to - from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static EASINGS: [Easing; 3] = [Easing::Linear, Easing::EaseOutCubic, Easing::Back];
+
+    #[test]
+    fn eases_from_0_to_1() {
+        for &easing in EASINGS.iter() {
+            assert!(easing.apply(0.0).abs() < 1e-9, "{:?} starts at {}", easing, easing.apply(0.0));
+            assert!((easing.apply(1.0) - 1.0).abs() < 1e-9, "{:?} ends at {}", easing, easing.apply(1.0));
+            assert_eq!(easing.name().parse(), Ok(easing));
+        }
+        assert!(Easing::Back.apply(0.8) > 1.0);
+    }
+
+    #[test]
+    fn progresses_within_its_span() {
+        let animation = Animation::new(2.0, 0.5, Easing::Back);
+        assert_eq!(animation.progress(1.0), 0.0);
+        assert_eq!(animation.progress(2.0), 0.0);
+        assert_eq!(animation.progress(2.5), 1.0);
+        assert_eq!(animation.interpolate(10.0, 20.0, 3.0), 20.0);
+        assert!(!animation.is_finished(2.4) && animation.is_finished(2.5));
+
+        // no time at all jumps straight to the end
+        let instant = Animation::new(2.0, 0.0, Easing::Linear);
+        assert_eq!(instant.progress(2.0), 1.0);
+    }
+}
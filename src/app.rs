@@ -1,66 +1,358 @@
+use std::collections::VecDeque;
 use std::path::{ Path, PathBuf };
+use std::time::{ SystemTime, UNIX_EPOCH };
 
 use piston_window::*;
 use opengl_graphics::GlGraphics;
 use opengl_graphics::Texture as GlTexture;
+use rust_2048::game::{ Direction, Game, SavedGame, Status };
+use rust_2048::replay::Replay;
+use best_score::BestScore;
 use board::Board;
+use hint::Hint;
 use number_renderer::NumberRenderer;
-use settings::Settings;
+use replay_player::ReplayPlayer;
+use rust_2048::settings::Settings;
+use rust_2048::theme::Theme;
+use storage;
+
+static BUTTON_WIDTH: f64 = 128.0;
+static BUTTON_HEIGHT: f64 = 40.0;
+static BUTTON_SPACING: f64 = 16.0;
+static OVERLAY_ALPHA: f32 = 0.73;
+static LABEL_OFFSET_Y: f64 = 6.0;
+static SAVE_FILENAME: &str = "savegame.json";
+
+/// What clicking a button on an overlay does.
+#[derive(Clone, Copy, PartialEq)]
+enum Action {
+    Restart,
+    KeepPlaying,
+}
 
 pub struct App<'a> {
     board: Board<'a>,
+    /// Where the replay of the current game is recorded.
+    replay_filename: String,
+    /// Set while watching a replay instead of playing.
+    player: Option<ReplayPlayer>,
+    /// Whether the computer plays.
+    autoplay: bool,
+    /// Time left before autoplay makes the next move.
+    autoplay_wait: f64,
+    /// Whether the computer made some of the moves of the current game,
+    /// which then doesn't count towards the best score.
+    assisted: bool,
+    /// Whether animations are turned off.
+    reduced_motion: bool,
+    /// Suggested move, shown until the board changes.
+    hint: Option<Hint>,
+    /// Moves made while tiles were still moving, made once they stop.
+    queued_moves: VecDeque<Direction>,
+    best_score: BestScore,
     number_renderer: Option<NumberRenderer>,
     settings: &'a Settings,
+    themes: Vec<Theme>,
+    /// Index of the current theme in `themes`.
+    theme: usize,
 
     logo: Option<GlTexture>,
     comment1: Option<GlTexture>,
     comment2: Option<GlTexture>,
-    window_background_color: [f32; 4],
+    score_label: Option<GlTexture>,
+    best_label: Option<GlTexture>,
+    game_over: Option<GlTexture>,
+    try_again: Option<GlTexture>,
+    you_win: Option<GlTexture>,
+    keep_going: Option<GlTexture>,
+
+    cursor: [f64; 2],
 }
 
 fn rgb2rgba(c: [f32; 3]) -> [f32; 4] { [c[0], c[1], c[2], 1.0] }
 
 impl<'a> App<'a> {
     pub fn new(settings: &'a Settings) -> App<'a> {
+        // a seed asked for explicitly starts that game rather than the saved one
+        let mut board = match settings.seed {
+            Some(_) => Board::new(settings),
+            None => App::load_board(settings).unwrap_or_else(|| Board::new(settings)),
+        };
+        let themes = Theme::load_all(&Path::new(&settings.asset_folder).join("themes"));
+        let theme = Theme::find(&themes, &settings.theme);
+
         App {
-            board: Board::new(settings),
+            replay_filename: App::replay_filename(&mut board),
+            board,
+            player: None,
+            autoplay: false,
+            autoplay_wait: 0.0,
+            assisted: false,
+            reduced_motion: settings.reduced_motion,
+            hint: None,
+            queued_moves: VecDeque::new(),
+            best_score: BestScore::load(),
             number_renderer: None,
             settings: settings,
+            themes,
+            theme,
 
             logo: None,
             comment1: None,
             comment2: None,
-            window_background_color: [1.0, 1.0, 1.0, 1.0],
+            score_label: None,
+            best_label: None,
+            game_over: None,
+            try_again: None,
+            you_win: None,
+            keep_going: None,
+
+            cursor: [0.0, 0.0],
         }
     }
 
+    fn theme(&self) -> &Theme {
+        &self.themes[self.theme]
+    }
+
+    /// Switches to the next theme, back to the first one after the last.
+    fn next_theme(&mut self) {
+        self.theme = (self.theme + 1) % self.themes.len();
+    }
+
     fn render_ui(&self, c: &Context, gl: &mut GlGraphics) {		
-        Image::new_color(rgb2rgba(self.settings.text_dark_color))
+        let theme = self.theme();
+        Image::new_color(rgb2rgba(theme.text_dark_color))
             .draw(self.logo.iter().next().unwrap(),
                   &DrawState::default(),
                   c.trans(self.settings.board_padding,self.settings.board_padding).transform,
                   gl);
 
-        Rectangle::new(rgb2rgba(self.settings.label_color))
-            .draw(self.settings.best_rect,
-                  &DrawState::default(),
-                  c.transform,
-                  gl);
+        let score_popups = self.board.score_popups();
+        let score_label = self.score_label.as_ref().unwrap();
+        self.render_score_box(self.settings.score_rect, score_label, self.board.score(), score_popups.score_scale(), c, gl);
+        let best_label = self.best_label.as_ref().unwrap();
+        self.render_score_box(self.settings.best_rect, best_label, self.best_score.score, 1.0, c, gl);
+        score_popups.render(theme, self.number_renderer.as_ref().unwrap(), self.settings.score_rect, c, gl);
 
         let comment1_offset_y = self.settings.comment1_offset_y;
         let comment1 = self.comment1.as_ref().unwrap();
-        App::render_comment(self.settings, comment1, comment1_offset_y, c, gl);
+        App::render_comment(self.settings, theme, comment1, comment1_offset_y, c, gl);
         let comment2_offset_y = self.settings.comment2_offset_y;
         let comment2 = self.comment2.as_ref().unwrap();
-        App::render_comment(self.settings, comment2, comment2_offset_y, c, gl);
+        App::render_comment(self.settings, theme, comment2, comment2_offset_y, c, gl);
+    }
+
+    /// Draws `score` under `label` in a box filling `rect`, the score scaled
+    /// by `scale`.
+    fn render_score_box(&self, rect: [f64; 4], label: &GlTexture, score: i32, scale: f64,
+                        c: &Context, gl: &mut GlGraphics) {
+        let theme = self.theme();
+        Rectangle::new(rgb2rgba(theme.label_color))
+            .draw(rect,
+                  &DrawState::default(),
+                  c.transform,
+                  gl);
+
+        let (width, height) = label.get_size();
+        let label_bottom = rect[1] + LABEL_OFFSET_Y + height as f64;
+        Image::new_color(rgb2rgba(theme.text_light_color))
+            .draw(label,
+                  &DrawState::default(),
+                  c.trans(rect[0] + (rect[2] - width as f64) / 2.0, rect[1] + LABEL_OFFSET_Y).transform,
+                  gl);
+
+        let (center_x, center_y) = (rect[0] + rect[2] / 2.0, (label_bottom + rect[1] + rect[3]) / 2.0);
+        self.number_renderer.as_ref().unwrap().render(
+            score as u32,
+            0.0,
+            0.0,
+            rect[2] - 2.0 * LABEL_OFFSET_Y,
+            theme.text_light_color, &c.trans(center_x, center_y).scale(scale, scale), gl);
+    }
+
+    /// Title and buttons of the overlay covering the board, if any.
+    fn overlay(&self) -> Option<(&GlTexture, Vec<(Action, &GlTexture)>)> {
+        if self.board.is_locking() || self.player.is_some() {
+            return None;
+        }
+
+        let try_again = self.try_again.as_ref().unwrap();
+        match self.board.status() {
+            Status::Won => {
+                let keep_going = self.keep_going.as_ref().unwrap();
+                Some((self.you_win.as_ref().unwrap(),
+                      vec![(Action::KeepPlaying, keep_going), (Action::Restart, try_again)]))
+            },
+            Status::GameOver => {
+                Some((self.game_over.as_ref().unwrap(), vec![(Action::Restart, try_again)]))
+            },
+            _ => None,
+        }
+    }
+
+    fn render_overlay(&self, c: &Context, gl: &mut GlGraphics) {
+        let (title, buttons) = match self.overlay() {
+            Some(overlay) => overlay,
+            None => return,
+        };
+
+        let board_rect = self.board_rect();
+        let theme = self.theme();
+        let bg = theme.window_background_color;
+
+        Rectangle::new([bg[0], bg[1], bg[2], OVERLAY_ALPHA])
+            .draw(board_rect,
+                  &DrawState::default(),
+                  c.transform,
+                  gl);
+
+        let (width, height) = title.get_size();
+        let x = board_rect[0] + (board_rect[2] - width as f64) / 2.0;
+        let y = board_rect[1] + board_rect[3] / 2.0 - height as f64 - BUTTON_HEIGHT / 2.0;
+        Image::new_color(rgb2rgba(theme.text_dark_color))
+            .draw(title,
+                  &DrawState::default(),
+                  c.trans(x, y).transform,
+                  gl);
+
+        for (i, &(_, label)) in buttons.iter().enumerate() {
+            let button = self.button_rect(i, buttons.len());
+            Rectangle::new(rgb2rgba(theme.button_color))
+                .draw(button,
+                      &DrawState::default(),
+                      c.transform,
+                      gl);
+
+            let (width, height) = label.get_size();
+            let x = button[0] + (button[2] - width as f64) / 2.0;
+            let y = button[1] + (button[3] - height as f64) / 2.0;
+            Image::new_color(rgb2rgba(theme.text_light_color))
+                .draw(label,
+                      &DrawState::default(),
+                      c.trans(x, y).transform,
+                      gl);
+        }
+    }
+
+    fn board_rect(&self) -> [f64; 4] {
+        [self.settings.board_padding,
+         self.settings.board_padding + self.settings.board_offset_y,
+         self.settings.board_size[0],
+         self.settings.board_size[1]]
+    }
+
+    /// Rect of the `i`th of `count` buttons laid out in a row under the
+    /// overlay title.
+    fn button_rect(&self, i: usize, count: usize) -> [f64; 4] {
+        let board_rect = self.board_rect();
+        let total_width = count as f64 * BUTTON_WIDTH + (count - 1) as f64 * BUTTON_SPACING;
+        [board_rect[0] + (board_rect[2] - total_width) / 2.0 + i as f64 * (BUTTON_WIDTH + BUTTON_SPACING),
+         board_rect[1] + board_rect[3] / 2.0 + BUTTON_HEIGHT / 2.0,
+         BUTTON_WIDTH,
+         BUTTON_HEIGHT]
+    }
+
+    fn do_action(&mut self, action: Action) {
+        match action {
+            Action::Restart => self.set_board(Board::new(self.settings)),
+            Action::KeepPlaying => self.board.keep_playing(),
+        }
+    }
+
+    /// Starts playing another game, once the replay of the current one has
+    /// been recorded.
+    fn set_board(&mut self, mut board: Board<'a>) {
+        self.record_replay();
+        self.replay_filename = App::replay_filename(&mut board);
+        self.board = board;
+        self.board.set_instant(self.reduced_motion);
+        self.assisted = false;
+        self.hint = None;
+        self.queued_moves.clear();
+    }
+
+    /// A file for every game, named after when it started. The time is
+    /// noted down in new games and saved with them, so that a resumed game
+    /// goes on with the same file.
+    fn replay_filename(board: &mut Board) -> String {
+        let started = match board.started() {
+            Some(started) => started,
+            None => {
+                let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_secs()).unwrap_or(0);
+                board.set_started(now);
+                now
+            },
+        };
+        format!("replays/{}-{}.json", started, board.seed())
+    }
+
+    fn record_replay(&self) {
+        if self.player.is_some() || self.board.moves() == 0 {
+            return;
+        }
+
+        if let Err(e) = storage::save(&self.replay_filename, &self.board.replay()) {
+            println!("WARNING: Failed to record replay: {}", e);
+        }
+    }
+
+    /// Watches `replay` instead of playing, if it fits the board.
+    pub fn play_replay(&mut self, replay: Replay) {
+        if !App::fits_board(self.settings, replay.rules.width, replay.rules.height, "Replay") {
+            return;
+        }
+
+        match replay.start() {
+            Ok(game) => {
+                self.set_board(Board::with_game(self.settings, game));
+                self.player = Some(ReplayPlayer::new(replay));
+            },
+            Err(e) => println!("WARNING: Replay is invalid: {}", e),
+        }
+    }
+
+    /// Goes back to the saved game, or to a new one if there is none.
+    fn stop_replay(&mut self) {
+        let board = App::load_board(self.settings).unwrap_or_else(|| Board::new(self.settings));
+        // the replay is still playing so that it isn't recorded again
+        self.set_board(board);
+        self.player = None;
+    }
+
+    /// Plays from the position written in `notation`.
+    pub fn play_position(&mut self, notation: &str) {
+        match Board::from_notation(self.settings, notation) {
+            Ok(board) => self.set_board(board),
+            Err(e) => println!("WARNING: Position is invalid: {}", e),
+        }
+    }
+
+    /// Prints the current position, to be pasted into a bug report.
+    fn print_position(&self) {
+        match self.board.notation() {
+            Ok(notation) => println!("Position: {}", notation),
+            Err(e) => println!("WARNING: Failed to write down the position: {}", e),
+        }
+    }
+
+    /// Whether a game played on a `width` by `height` board can be shown.
+    /// What doesn't fit is ignored with a warning.
+    fn fits_board(settings: &Settings, width: i32, height: i32, what: &str) -> bool {
+        if width != settings.tile_width || height != settings.tile_height {
+            println!("WARNING: {} is played on a {}x{} board but the board is {}x{}, ignoring it.",
+                     what, width, height, settings.tile_width, settings.tile_height);
+            return false;
+        }
+        true
     }
 
-    fn render_comment(settings: &Settings, comment: &GlTexture, y: f64, c: &Context, gl: &mut GlGraphics) {
+    fn render_comment(settings: &Settings, theme: &Theme, comment: &GlTexture, y: f64, c: &Context, gl: &mut GlGraphics) {
         let (width, height) = comment.get_size();
         let w = settings.window_size[0] as f64 - 2.0 * settings.board_padding;
         let h = height as f64 * w / width as f64;
 
-        Image::new_color(rgb2rgba(settings.text_dark_color))
+        Image::new_color(rgb2rgba(theme.text_dark_color))
             .rect([settings.board_padding, y, w, h])
             .draw( comment,
                    &DrawState::default(),
@@ -68,6 +360,65 @@ impl<'a> App<'a> {
                    gl);
     }
 
+    /// Saves the current game to be resumed on next launch. A finished game
+    /// is not worth resuming, so its save is removed instead.
+    pub fn save_game(&self) {
+        if self.player.is_some() {
+            return;
+        }
+
+        self.record_replay();
+        let saved = if self.board.status() == Status::GameOver {
+            storage::remove(SAVE_FILENAME)
+        } else {
+            storage::save(SAVE_FILENAME, &self.board.save())
+        };
+
+        if let Err(e) = saved {
+            println!("WARNING: Failed to save game: {}", e);
+        }
+    }
+
+    pub fn load_game(&mut self) {
+        if let Some(board) = App::load_board(self.settings) {
+            self.set_board(board);
+        }
+    }
+
+    /// Resumes the saved game, if there is one which fits the board.
+    fn load_board(settings: &'a Settings) -> Option<Board<'a>> {
+        let saved: SavedGame = match storage::load(SAVE_FILENAME) {
+            Ok(Some(saved)) => saved,
+            Ok(None) => return None,
+            Err(e) => {
+                println!("WARNING: Failed to load saved game: {}", e);
+                return None;
+            },
+        };
+
+        if !App::fits_board(settings, saved.rules.width, saved.rules.height, "Saved game") {
+            return None;
+        }
+
+        match Game::from_saved(saved) {
+            Ok(game) => Some(Board::with_game(settings, game)),
+            Err(e) => {
+                println!("WARNING: Saved game is invalid: {}", e);
+                None
+            },
+        }
+    }
+
+    /// Window title, showing the seed of the current game so it can be
+    /// reported along with a bug.
+    pub fn title(&self) -> String {
+        match self.player {
+            Some(ref player) => format!("Rust-2048 ({})", player.describe(&self.board)),
+            None if self.autoplay => format!("Rust-2048 (seed {}, autoplay)", self.board.seed()),
+            None => format!("Rust-2048 (seed {})", self.board.seed()),
+        }
+    }
+
     pub fn load(&mut self) {
         let mut asset_root = PathBuf::new();
         asset_root.push(Path::new(&self.settings.asset_folder));
@@ -78,54 +429,244 @@ impl<'a> App<'a> {
         comment1_path.push(Path::new("comment1.png"));
         let mut comment2_path = asset_root.clone();
         comment2_path.push(Path::new("comment2.png"));
+        let mut score_label_path = asset_root.clone();
+        score_label_path.push(Path::new("score.png"));
+        let mut best_label_path = asset_root.clone();
+        best_label_path.push(Path::new("best.png"));
+        let mut game_over_path = asset_root.clone();
+        game_over_path.push(Path::new("game_over.png"));
+        let mut try_again_path = asset_root.clone();
+        try_again_path.push(Path::new("try_again.png"));
+        let mut you_win_path = asset_root.clone();
+        you_win_path.push(Path::new("you_win.png"));
+        let mut keep_going_path = asset_root.clone();
+        keep_going_path.push(Path::new("keep_going.png"));
 
         self.number_renderer = Some(NumberRenderer::new());
         let texture_settings = TextureSettings::new();
         self.logo = Some(GlTexture::from_path(&logo_path, &texture_settings).unwrap());
         self.comment1 = Some(GlTexture::from_path(&comment1_path, &texture_settings).unwrap());
         self.comment2 = Some(GlTexture::from_path(&comment2_path, &texture_settings).unwrap());
+        self.score_label = Some(GlTexture::from_path(&score_label_path, &texture_settings).unwrap());
+        self.best_label = Some(GlTexture::from_path(&best_label_path, &texture_settings).unwrap());
+        self.game_over = Some(GlTexture::from_path(&game_over_path, &texture_settings).unwrap());
+        self.try_again = Some(GlTexture::from_path(&try_again_path, &texture_settings).unwrap());
+        self.you_win = Some(GlTexture::from_path(&you_win_path, &texture_settings).unwrap());
+        self.keep_going = Some(GlTexture::from_path(&keep_going_path, &texture_settings).unwrap());
     }
 
     pub fn render(&mut self, args: &RenderArgs, gl: &mut GlGraphics) {
         let area = args.window_size;
         let ref c = Context::new_abs(area[0], area[1]);
 
-        let w_bg_col = self.window_background_color;
+        let theme = self.theme();
+        let w_bg_col = rgb2rgba(theme.window_background_color);
         let ref nr = self.number_renderer;
 
         gl.draw(args.viewport(), |_, gl| {
             clear(w_bg_col, gl);
             self.render_ui(c, gl);
-            self.board.render(nr.iter().next().unwrap(), c, gl);
+            self.board.render(theme, nr.iter().next().unwrap(), c, gl);
+            if let Some(ref hint) = self.hint {
+                hint.render(theme, nr.as_ref().unwrap(), self.board_rect(), c, gl);
+            }
+            self.render_overlay(c, gl);
         });
 
     }
 
     pub fn update(&mut self, args: &UpdateArgs) {
+        let instant = self.reduced_motion || self.outpaces_animations();
+        self.board.set_instant(instant);
         self.board.update(args.dt);
+
+        if let Some(ref mut player) = self.player {
+            player.update(&mut self.board, args.dt);
+            return;
+        }
+
+        if !self.board.is_locking() {
+            if let Some(direction) = self.queued_moves.pop_front() {
+                self.move_tiles(direction);
+            }
+        }
+
+        if self.autoplay {
+            self.autoplay(args.dt);
+        }
+
+        if self.counts_for_best_score() && self.board.score() > self.best_score.score {
+            self.best_score.score = self.board.score();
+            self.best_score.save();
+        }
+    }
+
+    /// Whether the current game was played by a human from a new board,
+    /// rather than from a position or with the computer's help.
+    fn counts_for_best_score(&self) -> bool {
+        !self.assisted && self.board.game().start().is_none()
+    }
+
+    /// Whether replay or autoplay moves come faster than their animations,
+    /// which would hold them back.
+    fn outpaces_animations(&self) -> bool {
+        let rate = match self.player {
+            Some(ref player) if player.is_paused() => return false,
+            Some(ref player) => player.speed(),
+            None if self.autoplay => self.settings.autoplay_rate,
+            None => return false,
+        };
+        1.0 / rate < self.settings.move_animation_time()
+    }
+
+    /// Makes the moves of the computer player at the autoplay rate. It
+    /// waits for the player to keep playing once the game is won, and
+    /// stops when no move is left.
+    fn autoplay(&mut self, dt: f64) {
+        if self.board.is_locking() || self.board.status() == Status::Won {
+            return;
+        }
+
+        self.autoplay_wait -= dt;
+        if self.autoplay_wait > 0.0 {
+            return;
+        }
+        self.autoplay_wait = 1.0 / self.settings.autoplay_rate;
+
+        match self.settings.ai.best_move(self.board.game()) {
+            Some(direction) => {
+                self.assisted = true;
+                self.move_tiles(direction);
+            },
+            None => self.autoplay = false,
+        }
+    }
+
+    /// Makes a move, or queues it while tiles are still moving.
+    fn move_tiles(&mut self, direction: Direction) {
+        if self.board.is_locking() {
+            if self.queued_moves.len() < self.settings.input_queue_length {
+                self.queued_moves.push_back(direction);
+            }
+            return;
+        }
+
+        self.board.move_tiles(direction);
+        self.hint = None;
+
+        if self.board.status() == Status::GameOver {
+            self.record_replay();
+        }
+    }
+
+    pub fn mouse_move(&mut self, pos: &[f64; 2]) {
+        self.cursor = *pos;
     }
 
     pub fn key_press(&mut self, args: &Button) {
-		use piston_window::Button::Keyboard;
-		
-        if *args == Keyboard(Key::Left) {
-            self.board.merge_from_right_to_left();
+		use piston_window::Button::{ Keyboard, Mouse };
+
+        if *args == Keyboard(Key::M) {
+            self.reduced_motion = !self.reduced_motion;
+        }
+
+        if *args == Keyboard(Key::T) {
+            self.next_theme();
+        }
+
+        if self.player.is_some() {
+            self.replay_key_press(args);
+            return;
+        }
+
+        if *args == Mouse(MouseButton::Left) {
+            let actions: Vec<Action> = match self.overlay() {
+                Some((_, buttons)) => buttons.iter().map(|&(action, _)| action).collect(),
+                None => Vec::new(),
+            };
+            let [x, y] = self.cursor;
+
+            for (i, &action) in actions.iter().enumerate() {
+                let button = self.button_rect(i, actions.len());
+                if x >= button[0] && x < button[0] + button[2]
+                && y >= button[1] && y < button[1] + button[3] {
+                    self.do_action(action);
+                }
+            }
+        }
+
+        if *args == Keyboard(Key::Return) && self.board.status() == Status::Won {
+            self.do_action(Action::KeepPlaying);
+        }
+
+        let direction = match *args {
+            Keyboard(Key::Left) => Some(Direction::Left),
+            Keyboard(Key::Right) => Some(Direction::Right),
+            Keyboard(Key::Up) => Some(Direction::Up),
+            Keyboard(Key::Down) => Some(Direction::Down),
+            _ => None,
+        };
+
+        if let Some(direction) = direction {
+            self.move_tiles(direction);
+        }
+
+        if *args == Keyboard(Key::A) {
+            self.autoplay = !self.autoplay;
+            self.autoplay_wait = 0.0;
+        }
+
+        if *args == Keyboard(Key::Z) {
+            self.board.undo();
+            self.hint = None;
+            self.queued_moves.clear();
+        }
+
+        if *args == Keyboard(Key::Y) {
+            self.board.redo();
+            self.hint = None;
+            self.queued_moves.clear();
+        }
+
+        if *args == Keyboard(Key::H) {
+            self.hint = Hint::new(&self.settings.ai, self.board.game());
         }
 
-        if *args == Keyboard(Key::Right) {
-            self.board.merge_from_left_to_right();
+        if *args == Keyboard(Key::S) {
+            self.save_game();
         }
 
-        if *args == Keyboard(Key::Up) {
-            self.board.merge_from_bottom_to_top();
+        if *args == Keyboard(Key::L) {
+            self.load_game();
         }
 
-        if *args == Keyboard(Key::Down) {
-            self.board.merge_from_top_to_bottom();
+        if *args == Keyboard(Key::P) {
+            self.print_position();
         }
 
         if *args == Keyboard(Key::Space) {
-            self.board = Board::new(self.settings);
+            self.do_action(Action::Restart);
+        }
+    }
+
+    /// Space pauses, left and right step through moves, up and down
+    /// change the speed, Q leaves the replay.
+    fn replay_key_press(&mut self, args: &Button) {
+        use piston_window::Button::Keyboard;
+
+        if *args == Keyboard(Key::Q) {
+            self.stop_replay();
+            return;
+        }
+
+        let player = self.player.as_mut().unwrap();
+        match *args {
+            Keyboard(Key::Space) => player.toggle_pause(),
+            Keyboard(Key::Right) => player.step_forward(&mut self.board),
+            Keyboard(Key::Left) => player.step_back(&mut self.board),
+            Keyboard(Key::Up) => player.faster(),
+            Keyboard(Key::Down) => player.slower(),
+            _ => {},
         }
     }
 }
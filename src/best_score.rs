@@ -0,0 +1,30 @@
+//! Best score, kept across sessions in a file next to the settings.
+
+use storage;
+
+static BEST_SCORE_FILENAME: &str = "best_score.json";
+
+#[derive(RustcEncodable, RustcDecodable)]
+pub struct BestScore {
+    pub score: i32,
+}
+
+impl BestScore {
+    pub fn load() -> BestScore {
+        match storage::load(BEST_SCORE_FILENAME) {
+            Ok(Some(best_score)) => best_score,
+            // no best score yet
+            Ok(None) => BestScore { score: 0 },
+            Err(e) => {
+                println!("WARNING: Failed to load best score: {}", e);
+                BestScore { score: 0 }
+            },
+        }
+    }
+
+    pub fn save(&self) {
+        if let Err(e) = storage::save(BEST_SCORE_FILENAME, self) {
+            println!("WARNING: Failed to save best score: {}", e);
+        }
+    }
+}
@@ -0,0 +1,254 @@
+//! Packed engine for the standard 4x4 board, for the searches of the
+//! computer player which need millions of moves per second. It only slides
+//! tiles: games, simulated ones too, spawn tiles and keep score in `Game`.
+//!
+//! The board fits a `u64`: 4 bits per cell, the exponent of its tile and 0
+//! for empty cells, row after row from the lowest bits. Moves look up
+//! every row, or every column once the board is transposed, in tables
+//! computed once for the 65536 possible rows.
+//!
+//! 4 bits hold tiles up to 32768, and two of them don't merge since their
+//! sum wouldn't fit, so only games whose tiles are all below 32768 are
+//! packed. Moves then give exactly the same boards and scores as `Game`.
+
+use std::sync::OnceLock;
+use game::{ self, Direction, Game };
+
+pub static SIZE: i32 = 4;
+/// Highest exponent a cell can hold.
+pub static MAX_EXPONENT: u8 = 15;
+
+/// Every row moved left and right, and what moving it scores.
+struct Tables {
+    left: Vec<u16>,
+    right: Vec<u16>,
+    /// Score of moving a row left, or the reversed row right.
+    score: Vec<u32>,
+}
+
+static TABLES: OnceLock<Tables> = OnceLock::new();
+
+fn unpack_row(row: u16) -> [u8; 4] {
+    [(row & 0xf) as u8, (row >> 4 & 0xf) as u8, (row >> 8 & 0xf) as u8, (row >> 12 & 0xf) as u8]
+}
+
+fn pack_row(cells: [u8; 4]) -> u16 {
+    u16::from(cells[0]) | u16::from(cells[1]) << 4 | u16::from(cells[2]) << 8 | u16::from(cells[3]) << 12
+}
+
+fn reverse_row(row: u16) -> u16 {
+    let cells = unpack_row(row);
+    pack_row([cells[3], cells[2], cells[1], cells[0]])
+}
+
+/// Packs the tiles of a row towards its first cell, merging each tile into
+/// the one before it when they are equal and that one isn't the result of
+/// a merge already, as `Game` does.
+fn move_row_left(row: u16) -> (u16, u32) {
+    let mut packed = [0u8; 4];
+    let mut len = 0;
+    let mut merged = false;
+    let mut score = 0;
+
+    for &exponent in unpack_row(row).iter() {
+        if exponent == 0 {
+            continue;
+        }
+
+        if !merged && len > 0 && packed[len - 1] == exponent && exponent < MAX_EXPONENT {
+            packed[len - 1] += 1;
+            score += 1 << packed[len - 1];
+            merged = true;
+        } else {
+            packed[len] = exponent;
+            len += 1;
+            merged = false;
+        }
+    }
+
+    (pack_row(packed), score)
+}
+
+impl Tables {
+    fn get() -> &'static Tables {
+        TABLES.get_or_init(|| {
+            let mut tables = Tables {
+                left: vec![0; 1 << 16],
+                right: vec![0; 1 << 16],
+                score: vec![0; 1 << 16],
+            };
+
+            for row in 0..=u16::MAX {
+                let (left, score) = move_row_left(row);
+                tables.left[row as usize] = left;
+                tables.score[row as usize] = score;
+                tables.right[reverse_row(row) as usize] = reverse_row(left);
+            }
+            tables
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Bitboard(pub u64);
+
+impl Bitboard {
+    /// Packs a 4x4 game, `None` for other sizes and for tiles which aren't
+    /// powers of two below 2^`MAX_EXPONENT`, which couldn't merge.
+    pub fn from_game(game: &Game) -> Option<Bitboard> {
+        if game.width() != SIZE || game.height() != SIZE {
+            return None;
+        }
+
+        let mut board = Bitboard(0);
+        for tile in game.tiles() {
+            let exponent = game::exponent(tile.score);
+            if tile.score < 2 || tile.score.count_ones() != 1 || exponent >= MAX_EXPONENT {
+                return None;
+            }
+            board = board.with(tile.tile_x, tile.tile_y, exponent);
+        }
+        Some(board)
+    }
+
+    /// Exponent of the tile at (x, y), 0 for an empty cell.
+    pub fn get(self, x: i32, y: i32) -> u8 {
+        (self.0 >> (4 * (y * SIZE + x)) & 0xf) as u8
+    }
+
+    /// The board with the tile at (x, y) replaced by one of `exponent`.
+    pub fn with(self, x: i32, y: i32, exponent: u8) -> Bitboard {
+        let shift = 4 * (y * SIZE + x);
+        Bitboard(self.0 & !(0xf << shift) | u64::from(exponent & 0xf) << shift)
+    }
+
+    /// Exponent of every cell, row after row.
+    pub fn cells(self) -> [u8; 16] {
+        let mut cells = [0; 16];
+        for (i, cell) in cells.iter_mut().enumerate() {
+            *cell = (self.0 >> (4 * i) & 0xf) as u8;
+        }
+        cells
+    }
+
+    pub fn empty_cells(self) -> u32 {
+        self.cells().iter().filter(|&&exponent| exponent == 0).count() as u32
+    }
+
+    /// Swaps rows and columns.
+    pub fn transpose(self) -> Bitboard {
+        let x = self.0;
+        let a1 = x & 0xf0f0_0f0f_f0f0_0f0f;
+        let a2 = x & 0x0000_f0f0_0000_f0f0;
+        let a3 = x & 0x0f0f_0000_0f0f_0000;
+        let a = a1 | a2 << 12 | a3 >> 12;
+        let b1 = a & 0xff00_ff00_00ff_00ff;
+        let b2 = a & 0x00ff_00ff_0000_0000;
+        let b3 = a & 0x0000_0000_ff00_ff00;
+        Bitboard(b1 | b2 >> 24 | b3 << 24)
+    }
+
+    /// The board after sliding towards `direction`, without spawning a
+    /// tile, and the score of the move.
+    pub fn slide(self, direction: Direction) -> (Bitboard, u32) {
+        let tables = Tables::get();
+        let (board, table, reversed) = match direction {
+            Direction::Left => (self, &tables.left, false),
+            Direction::Right => (self, &tables.right, true),
+            Direction::Up => (self.transpose(), &tables.left, false),
+            Direction::Down => (self.transpose(), &tables.right, true),
+        };
+
+        let mut moved = 0;
+        let mut score = 0;
+        for y in 0..4 {
+            let row = (board.0 >> (16 * y) & 0xffff) as u16;
+            moved |= u64::from(table[row as usize]) << (16 * y);
+            score += tables.score[if reversed { reverse_row(row) } else { row } as usize];
+        }
+
+        let moved = Bitboard(moved);
+        match direction {
+            Direction::Up | Direction::Down => (moved.transpose(), score),
+            Direction::Left | Direction::Right => (moved, score),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+    use game::{ Direction, Game, Rules };
+    use notation::{ Position, Turn };
+    use rng::GameRng;
+    use super::*;
+
+    fn game(cells: Vec<u8>) -> Game {
+        let rules = Rules {
+            spawn_per_move: 0,
+            ..Rules::default()
+        };
+        let position = Position {
+            width: SIZE,
+            height: SIZE,
+            cells,
+            score: 0,
+            turn: Turn::Move,
+        };
+        Game::from_position(rules, 0, &position).unwrap()
+    }
+
+    // not through `from_game`, since merges may reach 2^`MAX_EXPONENT`
+    fn exponents(game: &Game) -> [u8; 16] {
+        let mut cells = [0; 16];
+        for tile in game.tiles() {
+            cells[(tile.tile_y * SIZE + tile.tile_x) as usize] = game::exponent(tile.score);
+        }
+        cells
+    }
+
+    #[test]
+    fn slides_like_game() {
+        let mut rng = GameRng::new(2048);
+
+        for _ in 0..20000 {
+            // mostly low exponents, so that rows have equal tiles to merge
+            let cells: Vec<u8> = (0..16).map(|_| {
+                if rng.gen_range(0, 4) == 0 { rng.gen_range(0, MAX_EXPONENT) } else { rng.gen_range(0, 4) }
+            }).collect();
+            let board = Bitboard::from_game(&game(cells.clone())).unwrap();
+
+            for &direction in Direction::all().iter() {
+                let mut game = game(cells.clone());
+                let result = game.move_tiles(direction);
+                let (slid, score) = board.slide(direction);
+
+                assert_eq!(exponents(&game), slid.cells(), "{:?} towards {:?}", cells, direction);
+                assert_eq!(result.score_gained as u32, score, "{:?} towards {:?}", cells, direction);
+            }
+        }
+    }
+
+    #[test]
+    fn leaves_out_tiles_which_cannot_merge() {
+        let mut cells = vec![0; 16];
+        cells[0] = MAX_EXPONENT;
+        cells[1] = MAX_EXPONENT;
+        assert_eq!(Bitboard::from_game(&game(cells)), None);
+    }
+
+    #[test]
+    fn transposes() {
+        let mut rng = GameRng::new(0);
+
+        for _ in 0..1000 {
+            let board = Bitboard(rng.next_u64());
+            let transposed = board.transpose();
+            for y in 0..SIZE {
+                for x in 0..SIZE {
+                    assert_eq!(transposed.get(y, x), board.get(x, y));
+                }
+            }
+        }
+    }
+}
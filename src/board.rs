@@ -1,402 +1,262 @@
-use std::collections::HashSet;
 use rand::random;
 use piston_window::*;
 use opengl_graphics::GlGraphics;
+use rust_2048::game::{ Direction, Game, MoveResult, SavedGame, Status };
+use rust_2048::notation::Position;
+use rust_2048::replay::Replay;
 use number_renderer::NumberRenderer;
-use settings::Settings;
+use score_popup::ScorePopups;
+use rust_2048::settings::Settings;
+use rust_2048::theme::Theme;
 use tile::{ Tile, TileState };
 
 fn rgb2rgba(c: [f32; 3]) -> [f32; 4] { [c[0], c[1], c[2], 1.0] }
 
+/// Draws a `Game` and animates its tiles between moves.
 pub struct Board<'a> {
-    tiles: Vec<Tile<'a>>,
-    score: i32,
+    game: Game,
+    /// Tiles indexed by cell, row after row.
+    tiles: Vec<Option<Tile<'a>>>,
+    /// Pairs of tiles sliding into the same cell, combined once both arrived.
+    merging: Vec<[Tile<'a>; 2]>,
+    /// Seconds since the board was made, the clock of the animations.
+    time: f64,
+    /// Whether moves show up right away, without animations.
+    instant: bool,
+    score_popups: ScorePopups,
     settings: &'a Settings,
 }
 
 impl<'a> Board<'a> {
     pub fn new(settings: &'a Settings) -> Board<'a> {
+        Board::with_game(settings, Game::new(settings.rules(), settings.seed.unwrap_or_else(random)))
+    }
+
+    /// Shows `game`, which must have the board size of `settings`.
+    pub fn with_game(settings: &'a Settings, game: Game) -> Board<'a> {
         let mut board = Board {
-            tiles: Vec::<Tile>::new(),
-            score: 0,
-            settings: settings,
+            game,
+            tiles: Vec::new(),
+            merging: Vec::new(),
+            time: 0.0,
+            instant: settings.reduced_motion,
+            score_popups: ScorePopups::new(),
+            settings,
         };
-        board.generate_tile();
-        board.generate_tile();
+        if board.instant {
+            board.sync_tiles(Tile::new_static);
+        } else {
+            board.sync_tiles(|settings, score, x, y| Tile::new(settings, score, x, y, 0.0));
+        }
         board
     }
 
-    pub fn generate_tile(&mut self) {
-        if self.tiles.len() == (self.settings.tile_width * self.settings.tile_height) as usize {
-            return;
+    /// Starts from the position written in `notation`, which must have the
+    /// board size of `settings`.
+    pub fn from_notation(settings: &'a Settings, notation: &str) -> Result<Board<'a>, String> {
+        let position: Position = notation.parse()?;
+        if position.width != settings.tile_width || position.height != settings.tile_height {
+            return Err(format!("the position is {}x{} but the board is {}x{}",
+                               position.width, position.height, settings.tile_width, settings.tile_height));
         }
 
-        loop {
-            let x = (random::<u32>() % self.settings.tile_width as u32) as i32;
-            let y = (random::<u32>() % self.settings.tile_height as u32) as i32;
-
-            if self.get_tile(x, y).is_none() {
-                let score = if random::<u32>() % 10 == 0 {
-                    4
-                } else {
-                    2
-                };
-                self.tiles.push(Tile::new(self.settings, score, x, y));
-                break;
-            }
-        }
+        let game = Game::from_position(settings.rules(), settings.seed.unwrap_or_else(random), &position)?;
+        Ok(Board::with_game(settings, game))
+    }
+
+    /// Notation of the current position.
+    pub fn notation(&self) -> Result<String, String> {
+        self.game.position().map(|position| position.to_string())
     }
 
     pub fn update(&mut self, dt: f64) {
-        for tile in self.tiles.iter_mut() {
-            tile.update(dt);
-        }
+        self.time += dt;
+        let now = self.time;
+        self.score_popups.update(now);
 
-        if self.is_locking() {
-            return;
+        for tile in self.tiles.iter_mut().filter_map(|tile| tile.as_mut()) {
+            tile.update(now);
         }
 
-        let mut score_to_added = 0;
-        let mut tiles_need_removed = HashSet::<usize>::new();
-        let mut tiles_need_added = Vec::<Tile>::new();
-
-        for i in 0..self.tiles.len() {
-            let tile1 = &self.tiles[i];
-
-            if tile1.status != TileState::TileStatic {
-                continue;
-            }
-
-            for j in i+1..self.tiles.len() {
-                let tile2 = &self.tiles[j];
-
-                if tile2.status != TileState::TileStatic
-                  || tile1.tile_x != tile2.tile_x
-                  || tile1.tile_y != tile2.tile_y {
-                    continue;
-                }
-
-                tiles_need_removed.insert(i);
-                tiles_need_removed.insert(j);
-                tiles_need_added.push(Tile::new_combined(self.settings, tile1.score + tile2.score, tile1.tile_x, tile1.tile_y));
-                score_to_added += tile1.score + tile2.score;
-                break;
-            }
+        for pair in self.merging.iter_mut() {
+            pair[0].update(now);
+            pair[1].update(now);
         }
 
-        if tiles_need_removed.len() > 0 {
-            let mut tiles = Vec::<Tile>::new();
+        let (arrived, merging): (Vec<_>, Vec<_>) = self.merging.drain(..).partition(|pair| {
+            pair[0].status == TileState::TileStatic && pair[1].status == TileState::TileStatic
+        });
+        self.merging = merging;
 
-            for i in 0..self.tiles.len() {
-                if !tiles_need_removed.contains(&i) {
-                    tiles.push(self.tiles[i].clone());
-                }
-            }
-
-            // better but unstable:          tiles.append(&mut tiles_need_added);
-            while let Some(tile_to_add) = tiles_need_added.pop() {
-                tiles.push(tile_to_add);
-            }
-
-            self.tiles = tiles;
-            self.add_score(score_to_added);
+        for pair in arrived {
+            let (x, y) = (pair[0].tile_x, pair[0].tile_y);
+            let i = self.index(x, y);
+            self.tiles[i] = Some(Tile::new_combined(self.settings, pair[0].score + pair[1].score, x, y, now));
         }
     }
 
-    pub fn render(&self, number_renderer: &NumberRenderer, c: &Context, gl: &mut GlGraphics) {
-        number_renderer.render(
-            self.score as u32,
-            self.settings.best_rect[0] + self.settings.best_rect[2] / 2.0,
-            self.settings.best_rect[1] + self.settings.best_rect[3] / 2.0,
-            self.settings.best_rect[2],
-            self.settings.text_light_color, c, gl);
+    pub fn render(&self, theme: &Theme, number_renderer: &NumberRenderer, c: &Context, gl: &mut GlGraphics) {
+        self.render_board(theme, c, gl);
+        self.render_tiles(theme, number_renderer, c, gl);
+    }
 
-        self.render_board(c, gl);
-        self.render_tiles(number_renderer, c, gl);
+    pub fn game(&self) -> &Game {
+        &self.game
     }
 
-    pub fn merge_from_bottom_to_top(&mut self) {
-        let height = self.settings.tile_height;
-        self.merge_col(0, height, 1);
+    pub fn score(&self) -> i32 {
+        self.game.score()
     }
 
-    pub fn merge_from_top_to_bottom(&mut self) {
-        let height = self.settings.tile_height;
-        self.merge_col(height - 1, -1, -1);
+    /// Points scored by the last moves, shown over the score.
+    pub fn score_popups(&self) -> &ScorePopups {
+        &self.score_popups
     }
 
-    fn merge_col(&mut self, y_start: i32, y_end: i32, y_step: i32) {
-        if self.is_locking() {
-            println!("return");
-            return;
-        }
+    pub fn save(&self) -> SavedGame {
+        self.game.save()
+    }
 
-        let mut need_generate = false;
-        let mut steps: Vec<i32> = Vec::with_capacity(self.settings.tile_height as usize);
+    pub fn replay(&self) -> Replay {
+        self.game.replay()
+    }
 
-        let mut next_step = y_start;
+    pub fn moves(&self) -> u32 {
+        self.game.moves()
+    }
 
-        if y_step < 0 {
-            while next_step > y_end {
-                steps.push(next_step); next_step += y_step
-            }
-        } else {
-            while next_step < y_end {
-                steps.push(next_step); next_step += y_step
-            }
-        }
+    pub fn status(&self) -> Status {
+        self.game.status()
+    }
 
-        loop {
-            // move all tiles to right place
-            for col in 0 .. self.settings.tile_width {
-                // TODO: replace steps by (y_start .. y_end).step_by(y_step) if step_by becomes stable
-                for row  in steps.to_vec() {
-                    match self.get_mut_tile(col, row) {
-                        None => {
-                            match self.get_mut_next_tile(col, row, 0, y_step) {
-                                Some(ref mut tile) => {
-                                    println!("move ({}, {}) to ({}, {})",
-                                        tile.tile_x, tile.tile_y, col, row);
-                                    need_generate = true;
-                                    tile.start_moving(col, row);
-                                },
-                                _ => {},
-                            }
-                        },
-                        _ => {},
-                    }
-                }
-            }
+    pub fn seed(&self) -> u64 {
+        self.game.seed()
+    }
 
-            let mut did_merged = false;
-            for col in 0..self.settings.tile_width {
-                let mut found = false;
-                let mut sx = 0;
-                let mut sy = 0;
-                let mut dx = 0;
-                let mut dy = 0;
-                for row  in steps.to_vec() {
-                    match self.get_tile(col, row) {
-                        Some(ref d_tile) => {
-                            match self.get_next_tile(col, row, 0, y_step) {
-                                Some(ref s_tile)
-                                if d_tile.score == s_tile.score
-                                && self.get_tile_count(d_tile.tile_x, d_tile.tile_y) == 1 => {
-                                    found = true;
-                                    dx = d_tile.tile_x;
-                                    dy = d_tile.tile_y;
-                                    sx = s_tile.tile_x;
-                                    sy = s_tile.tile_y;
-                                    break;
-                                },
-                                _ => {},
-                            }
-                        },
-                        None => {
-                            break;
-                        }
-                    }
-                }
-
-                if found {
-                    need_generate = true;
-                    did_merged = true;
-                    let tile = self.get_mut_tile(sx, sy).unwrap();
-                    tile.start_moving(dx, dy);
-                    println!("merge ({}, {}) to ({}, {})", sx, sy, dx, dy);
-                }
-            }
+    pub fn started(&self) -> Option<u64> {
+        self.game.started()
+    }
 
-            if !did_merged {
-                break;
-            }
-        }
+    pub fn set_started(&mut self, started: u64) {
+        self.game.set_started(started);
+    }
+
+    pub fn keep_playing(&mut self) {
+        self.game.keep_playing();
+    }
 
-        if need_generate {
-            self.generate_tile();
+    /// Turns animations off, ending the ones in progress, or back on.
+    pub fn set_instant(&mut self, instant: bool) {
+        if instant && !self.instant {
+            self.sync_tiles(Tile::new_static);
+            self.score_popups.clear();
         }
+        self.instant = instant;
     }
 
-    pub fn merge_from_left_to_right(&mut self) {
-        let width = self.settings.tile_width;
-        self.merge_row(width - 1, -1, -1);
+    pub fn undo(&mut self) {
+        self.game.undo();
+        self.sync_tiles(Tile::new_static);
     }
 
-    pub fn merge_from_right_to_left(&mut self) {
-        let width = self.settings.tile_width;
-        self.merge_row(0, width, 1);
+    pub fn redo(&mut self) {
+        self.game.redo();
+        self.sync_tiles(Tile::new_static);
     }
 
-    fn merge_row(&mut self, x_start: i32, x_end: i32, x_step: i32) {
+    pub fn move_tiles(&mut self, direction: Direction) {
         if self.is_locking() {
             return;
         }
 
-        let mut need_generate = false;
-        let mut steps: Vec<i32> = Vec::with_capacity(self.settings.tile_width as usize);
-        let mut next_step = x_start;
+        let result = self.game.move_tiles(direction);
+        self.animate_move(&result);
+    }
 
-        if x_step < 0 {
-            while next_step > x_end {
-                steps.push(next_step); next_step += x_step
-            }
-        } else {
-            while next_step < x_end {
-                steps.push(next_step); next_step += x_step
-            }
+    /// Makes the `i`th move of `replay`, which this game must be played from.
+    pub fn play_replay_move(&mut self, replay: &Replay, i: usize) {
+        if self.is_locking() {
+            return;
         }
 
-        loop {
-            // move all tiles to right place
-            for row in 0..self.settings.tile_height {
-                for col  in steps.to_vec() {
-                    match self.get_mut_tile(col, row) {
-                        None => {
-                            match self.get_mut_next_tile(col, row, x_step, 0) {
-                                Some(ref mut tile) => {
-                                    println!("move ({}, {}) to ({}, {})", tile.tile_x, tile.tile_y, col, row);
-                                    need_generate = true;
-                                    tile.start_moving(col, row);
-                                },
-                                _ => {},
-                            }
-                        },
-                        _ => {},
-                    }
-                }
-            }
+        let result = replay.play_move(&mut self.game, i);
+        self.animate_move(&result);
+    }
 
-            // merge
-            let mut did_merged = false;
-            for row in 0..self.settings.tile_height {
-                let mut found = false;
-                let mut sx = 0;
-                let mut sy = 0;
-                let mut dx = 0;
-                let mut dy = 0;
-                for col in steps.to_vec() {
-                    match self.get_tile(col, row) {
-                        Some(ref d_tile) => {
-                            match self.get_next_tile(col, row, x_step, 0) {
-                                Some(ref s_tile)
-                                if d_tile.score == s_tile.score
-                                && self.get_tile_count(d_tile.tile_x, d_tile.tile_y) == 1 => {
-                                    found = true;
-                                    dx = d_tile.tile_x;
-                                    dy = d_tile.tile_y;
-                                    sx = s_tile.tile_x;
-                                    sy = s_tile.tile_y;
-                                    break;
-                                },
-                                _ => {},
-                            }
-                        },
-                        None => {
-                            break;
-                        }
-                    }
-                }
-
-                if found {
-                    need_generate = true;
-                    did_merged = true;
-                    let tile = self.get_mut_tile(sx, sy).unwrap();
-                    tile.start_moving(dx, dy);
-                    println!("merge ({}, {}) to ({}, {})", sx, sy, dx, dy);
-                }
-            }
+    /// Rebuilds the tiles from the game, each made by `new_tile`.
+    fn sync_tiles<F>(&mut self, new_tile: F) where F: Fn(&'a Settings, i32, i32, i32) -> Tile<'a> {
+        let settings = self.settings;
+        let mut tiles = vec![None; (self.game.width() * self.game.height()) as usize];
 
-            if !did_merged {
-                break;
-            }
+        for tile in self.game.tiles() {
+            tiles[self.index(tile.tile_x, tile.tile_y)] = Some(new_tile(settings, tile.score, tile.tile_x, tile.tile_y));
         }
 
-        if need_generate {
-            self.generate_tile();
-        }
+        self.tiles = tiles;
+        self.merging = Vec::new();
     }
 
-    fn is_locking(&self) -> bool {
-        for tile in self.tiles.iter() {
-            if tile.status != TileState::TileStatic {
-                return true;
-            }
+    /// Slides the tiles moved by a move from where they were. Merged tiles
+    /// slide in as two halves which `update` combines once both arrived.
+    fn animate_move(&mut self, result: &MoveResult) {
+        let settings = self.settings;
+        let now = self.time;
+        self.sync_tiles(Tile::new_static);
+        if self.instant {
+            return;
         }
-        false
-    }
 
-    /// Returns next tile right besides (x, y)
-    fn get_next_tile<'b>(&'b self, x: i32, y: i32, step_x: i32, step_y: i32) -> Option<&'b Tile<'a>> {
-        let mut x = x + step_x;
-        let mut y = y + step_y;
-        while x >= 0 && x < self.settings.tile_width
-        && y >= 0 && y < self.settings.tile_height {
-            let tile = self.get_tile(x, y);
-            if tile.is_some() {
-                return tile;
-            }
-            x += step_x;
-            y += step_y;
+        if result.score_gained > 0 {
+            self.score_popups.add(result.score_gained, now);
         }
-        None
-    }
 
-    fn get_mut_next_tile<'b>(&'b mut self, x: i32, y: i32, step_x: i32, step_y: i32) -> Option<&'b mut Tile<'a>> {
-        let mut x = x + step_x;
-        let mut y = y + step_y;
-        let mut found = false;
-        while x >= 0 && x < self.settings.tile_width
-        && y >= 0 && y < self.settings.tile_height {
-            let tile = self.get_tile(x, y);
-
-            if tile.is_some() {
-                found = true;
-                break;
-            }
-            x += step_x;
-            y += step_y;
+        for tile_move in result.moved.iter() {
+            let ((ox, oy), (x, y)) = (tile_move.from, tile_move.to);
+            let mut tile = Tile::new_static(settings, tile_move.score, ox, oy);
+            tile.start_moving(x, y, now);
+            let i = self.index(x, y);
+            self.tiles[i] = Some(tile);
         }
 
-        if found {
-            self.get_mut_tile(x, y)
-        } else {
-            None
+        for merge in result.merged.iter() {
+            let (x, y) = merge.position;
+            let half = |(ox, oy)| {
+                let mut half = Tile::new_static(settings, merge.score / 2, ox, oy);
+                half.start_moving(x, y, now);
+                half
+            };
+            self.merging.push([half(merge.sources[0]), half(merge.sources[1])]);
+            let i = self.index(x, y);
+            self.tiles[i] = None;
         }
-    }
 
-    fn get_tile<'b>(&'b self, x: i32, y: i32) -> Option<&'b Tile<'a>> {
-        for tile in self.tiles.iter() {
-            if tile.tile_x == x && tile.tile_y == y {
-                return Some(tile);
-            }
+        for spawn in result.spawned.iter() {
+            let (x, y) = spawn.position;
+            let i = self.index(x, y);
+            self.tiles[i] = Some(Tile::new(settings, spawn.score, x, y, now));
         }
+    }
 
-        None
+    fn index(&self, x: i32, y: i32) -> usize {
+        (y * self.game.width() + x) as usize
     }
 
-    fn get_mut_tile<'b>(&'b mut self, x: i32, y: i32) -> Option<&'b mut Tile<'a>> {
-        for tile in self.tiles.iter_mut() {
-            if tile.tile_x == x && tile.tile_y == y {
-                return Some(tile);
-            }
+    /// Whether tiles are still animating, during which moves are ignored.
+    pub fn is_locking(&self) -> bool {
+        if !self.merging.is_empty() {
+            return true;
         }
 
-        None
-    }
-
-    fn get_tile_count(&self, x: i32, y: i32) -> i32 {
-        let mut count = 0;
-        for tile in self.tiles.iter() {
-            if tile.tile_x == x && tile.tile_y == y {
-                count += 1;
+        for tile in self.tiles.iter().filter_map(|tile| tile.as_ref()) {
+            if tile.status != TileState::TileStatic {
+                return true;
             }
         }
-
-        count
+        false
     }
 
-    fn render_board(&self, c: &Context, gl: &mut GlGraphics) {
-        Rectangle::new(rgb2rgba(self.settings.label_color))
+    fn render_board(&self, theme: &Theme, c: &Context, gl: &mut GlGraphics) {
+        Rectangle::new(rgb2rgba(theme.label_color))
         .draw(
             [self.settings.board_padding,
             self.settings.board_padding + self.settings.board_offset_y,
@@ -412,7 +272,7 @@ impl<'a> Board<'a> {
         for _ in 0..self.settings.tile_height {
             for _ in 0..self.settings.tile_width {
                 Rectangle::new(
-                    rgb2rgba(self.settings.tiles_colors[0]))
+                    rgb2rgba(theme.tile_color(0)))
                     .draw([x, y, self.settings.tile_size, self.settings.tile_size],
                     &DrawState::default(),
                     c.transform,
@@ -426,14 +286,14 @@ impl<'a> Board<'a> {
         }
     }
 
-    fn render_tiles(&self, number_renderer: &NumberRenderer, c: &Context, gl: &mut GlGraphics) {
-        for tile in self.tiles.iter() {
-            tile.render(number_renderer, c, gl);
+    fn render_tiles(&self, theme: &Theme, number_renderer: &NumberRenderer, c: &Context, gl: &mut GlGraphics) {
+        for tile in self.tiles.iter().filter_map(|tile| tile.as_ref()) {
+            tile.render(theme, number_renderer, self.time, c, gl);
         }
-    }
 
-    fn add_score(&mut self, score: i32) {
-        self.score += score;
-        println!("Score: {}", self.score);
+        for pair in self.merging.iter() {
+            pair[0].render(theme, number_renderer, self.time, c, gl);
+            pair[1].render(theme, number_renderer, self.time, c, gl);
+        }
     }
 }
@@ -0,0 +1,201 @@
+//! Gym-style environment around the rules, for reinforcement learning.
+//!
+//! An episode is a game: `reset` starts one from a seed and `step` makes
+//! a move, returning the observation, the reward, whether the episode is
+//! done and more information on the game. The game goes on after the
+//! target tile is reached, so episodes end when no move is left.
+
+use game::{ self, Direction, Game, Rules, Status };
+
+/// Actions, in the order of action indices and masks.
+pub static ACTIONS: [Direction; 4] = [Direction::Up, Direction::Down, Direction::Left, Direction::Right];
+
+/// Weights of what a step is rewarded for, summed up into its reward.
+#[derive(Clone, Debug, PartialEq, RustcEncodable, RustcDecodable)]
+pub struct Reward {
+    /// Per point scored by the move.
+    pub score_delta: f64,
+    /// Per step of log2 of the highest tile gained by the move.
+    pub log_max_tile: f64,
+    /// Per move which changed the board.
+    pub survival: f64,
+    /// Per move which didn't change the board, usually a penalty.
+    pub illegal_move: f64,
+}
+
+impl Default for Reward {
+    fn default() -> Reward {
+        Reward {
+            score_delta: 1.0,
+            log_max_tile: 0.0,
+            survival: 0.0,
+            illegal_move: 0.0,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Info {
+    pub score: i32,
+    pub moves: u32,
+    pub max_tile: i32,
+    /// Whether the move changed the board. Moves which don't leave the game
+    /// as it was.
+    pub changed: bool,
+    /// Legal actions of the next step.
+    pub action_mask: [bool; 4],
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Step {
+    pub observation: Vec<u8>,
+    pub reward: f64,
+    pub done: bool,
+    pub info: Info,
+}
+
+pub struct Environment {
+    rules: Rules,
+    reward: Reward,
+    game: Game,
+}
+
+impl Environment {
+    /// Creates an environment playing by `rules`, which must pass
+    /// `Rules::check`, starting with the episode of seed 0.
+    pub fn new(rules: Rules, reward: Reward) -> Environment {
+        Environment {
+            game: episode(&rules, 0),
+            rules,
+            reward,
+        }
+    }
+
+    /// Starts a new episode and returns its first observation.
+    pub fn reset(&mut self, seed: u64) -> Vec<u8> {
+        self.game = episode(&self.rules, seed);
+        self.observation()
+    }
+
+    /// Makes a move and returns what came of it.
+    pub fn step(&mut self, direction: Direction) -> Step {
+        let max_tile_before = self.max_tile();
+        let result = self.game.move_tiles(direction);
+
+        if self.game.status() == Status::Won {
+            self.game.keep_playing();
+        }
+
+        let reward = if result.changed {
+            let max_tile_gained = game::exponent(self.max_tile()) - game::exponent(max_tile_before);
+            self.reward.score_delta * f64::from(result.score_gained)
+                + self.reward.log_max_tile * f64::from(max_tile_gained)
+                + self.reward.survival
+        } else {
+            self.reward.illegal_move
+        };
+
+        Step {
+            observation: self.observation(),
+            reward,
+            done: self.game.status() == Status::GameOver,
+            info: Info {
+                score: self.game.score(),
+                moves: self.game.moves(),
+                max_tile: self.max_tile(),
+                changed: result.changed,
+                action_mask: self.action_mask(),
+            },
+        }
+    }
+
+    /// Makes the move of action `action`, an index into `ACTIONS`, so it
+    /// panics for indices of 4 and up.
+    pub fn step_action(&mut self, action: usize) -> Step {
+        self.step(ACTIONS[action])
+    }
+
+    /// Whether each action of `ACTIONS` would change the board.
+    pub fn action_mask(&self) -> [bool; 4] {
+        let mut mask = [false; 4];
+        for (i, &direction) in ACTIONS.iter().enumerate() {
+            mask[i] = self.game.can_move_towards(direction);
+        }
+        mask
+    }
+
+    /// Exponent of the tile in every cell as `game::exponent` gives it, row
+    /// after row, 0 for empty cells.
+    pub fn observation(&self) -> Vec<u8> {
+        let mut cells = vec![0; (self.game.width() * self.game.height()) as usize];
+        for tile in self.game.tiles() {
+            cells[(tile.tile_y * self.game.width() + tile.tile_x) as usize] = game::exponent(tile.score);
+        }
+        cells
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    fn max_tile(&self) -> i32 {
+        self.game.tiles().map(|tile| tile.score).max().unwrap_or(0)
+    }
+}
+
+/// Game of the episode of `seed`, which keeps no history since learners
+/// make far too many moves to undo or replay them.
+fn episode(rules: &Rules, seed: u64) -> Game {
+    let mut game = Game::new(rules.clone(), seed);
+    game.forget_history();
+    game
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn from_position(rules: Rules, position: &str) -> Environment {
+        let reward = Reward {
+            score_delta: 1.0,
+            log_max_tile: 10.0,
+            survival: 0.5,
+            illegal_move: -5.0,
+        };
+        let mut environment = Environment::new(rules.clone(), reward);
+        environment.game = Game::from_position(rules, 0, &position.parse().unwrap()).unwrap();
+        environment
+    }
+
+    #[test]
+    fn rewards_moves() {
+        let mut environment = from_position(Rules::default(), "4x4 1100/0000/0000/0000 0 m");
+        assert_eq!(environment.action_mask(), [false, true, true, true]);
+
+        // 4 points, one step of exponent up to the 4 and surviving
+        let step = environment.step_action(2);
+        assert_eq!(step.reward, 4.0 + 10.0 + 0.5);
+        assert_eq!(step.observation[0], 2);
+        assert!(!step.done);
+        assert_eq!((step.info.score, step.info.moves, step.info.max_tile, step.info.changed), (4, 1, 4, true));
+        assert_eq!(step.info.action_mask, environment.action_mask());
+
+        let mut environment = from_position(Rules::default(), "4x4 1000/0000/0000/0000 0 m");
+        let step = environment.step(Direction::Left);
+        assert_eq!(step.reward, -5.0);
+        assert_eq!(step.observation, environment.observation());
+        assert_eq!((step.info.moves, step.info.changed), (0, false));
+    }
+
+    #[test]
+    fn ends_when_no_move_is_left() {
+        let rules = Rules { spawn_table: vec![(32, 1)], ..Rules::default() };
+        let mut environment = from_position(rules, "2x2 12/30 0 m");
+        assert_eq!(environment.action_mask(), [false, true, false, true]);
+
+        let step = environment.step(Direction::Right);
+        assert!(step.done);
+        assert_eq!(step.observation, [1, 2, 5, 3]);
+        assert_eq!(step.info.action_mask, [false; 4]);
+    }
+}
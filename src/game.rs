@@ -0,0 +1,1036 @@
+//! Rules of the game: the grid of tiles, the score, moves and spawning.
+//!
+//! Nothing in here knows about rendering, so the rules can be driven
+//! and tested without opening a window.
+
+use std::str::FromStr;
+use rand::Rng;
+use notation::{ Position, Turn };
+use replay::Replay;
+use rng::GameRng;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tile {
+    pub score: i32,
+    pub tile_x: i32,
+    pub tile_y: i32,
+    /// Where the tile was before the current move, `None` if it spawned since.
+    origin: Option<(i32, i32)>,
+    /// Where the two tiles combined into this one by the current move came from.
+    merged_from: Option<[(i32, i32); 2]>,
+}
+
+impl Tile {
+    fn new(score: i32, tile_x: i32, tile_y: i32) -> Tile {
+        Tile {
+            score,
+            tile_x,
+            tile_y,
+            origin: None,
+            merged_from: None,
+        }
+    }
+}
+
+/// A tile sliding to another cell without being merged.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TileMove {
+    pub from: (i32, i32),
+    pub to: (i32, i32),
+    pub score: i32,
+}
+
+/// Two tiles combined into one.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TileMerge {
+    pub sources: [(i32, i32); 2],
+    pub position: (i32, i32),
+    /// Score of the resulting tile.
+    pub score: i32,
+}
+
+/// A tile appearing on an empty cell.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TileSpawn {
+    pub position: (i32, i32),
+    pub score: i32,
+}
+
+/// What a move did to the board.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MoveResult {
+    pub moved: Vec<TileMove>,
+    pub merged: Vec<TileMerge>,
+    pub spawned: Vec<TileSpawn>,
+    pub score_gained: i32,
+    /// Whether any tile moved. Moves which don't change the board don't
+    /// spawn a tile either.
+    pub changed: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, RustcEncodable, RustcDecodable)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Direction {
+    pub fn all() -> [Direction; 4] {
+        [Direction::Up, Direction::Down, Direction::Left, Direction::Right]
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Direction::Up => "up",
+            Direction::Down => "down",
+            Direction::Left => "left",
+            Direction::Right => "right",
+        }
+    }
+
+    /// (x, y) offset of one cell towards this direction.
+    pub fn step(self) -> (i32, i32) {
+        match self {
+            Direction::Up => (0, -1),
+            Direction::Down => (0, 1),
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+        }
+    }
+}
+
+impl FromStr for Direction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Direction, String> {
+        Direction::all().iter().cloned()
+            .find(|direction| direction.name() == s)
+            .ok_or_else(|| format!("invalid direction {}", s))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, RustcEncodable, RustcDecodable)]
+pub enum Status {
+    Playing,
+    /// A merge has just produced the target tile. Moves are ignored until
+    /// the player chooses to keep playing.
+    Won,
+    /// The target tile has been reached and the game goes on endlessly.
+    KeepPlaying,
+    /// The board is full and no two adjacent tiles are equal.
+    GameOver,
+}
+
+/// Whether tiles of `a` and `b` merge: they are equal and their sum still
+/// fits a score.
+pub fn can_merge(a: i32, b: i32) -> bool {
+    a == b && a.checked_add(b).is_some()
+}
+
+/// Exponent of the highest power of two up to `score`, which tells tiles
+/// apart by size: exact for the usual tiles, rounded down for the others.
+/// Scores of 1 get 1, as 2 does, so that only empty cells get 0.
+pub fn exponent(score: i32) -> u8 {
+    if score <= 0 {
+        0
+    } else {
+        (31 - score.leading_zeros()).max(1) as u8
+    }
+}
+
+/// Largest width and height of a board.
+pub static MAX_SIZE: i32 = 64;
+
+/// Everything which sets a variant of the game apart.
+#[derive(Clone, Debug, PartialEq, RustcEncodable, RustcDecodable)]
+pub struct Rules {
+    pub width: i32,
+    pub height: i32,
+    /// Score a tile has to reach to win the game.
+    pub target: i32,
+    /// Scores of the tiles which can spawn, each with its relative weight.
+    /// The scores must be positive and the weights must not all be zero.
+    pub spawn_table: Vec<(i32, u32)>,
+    /// Number of tiles spawned after each move.
+    pub spawn_per_move: u32,
+    /// Number of tiles on a new board.
+    pub start_tiles: u32,
+}
+
+impl Rules {
+    /// Checks the rules can be played, as they may come from a file.
+    pub fn check(&self) -> Result<(), String> {
+        if self.width <= 0 || self.height <= 0 || self.width > MAX_SIZE || self.height > MAX_SIZE {
+            return Err(format!("invalid board size {}x{}, boards go from 1x1 to {}x{}",
+                               self.width, self.height, MAX_SIZE, MAX_SIZE));
+        }
+        if self.spawn_table.iter().all(|&(_, weight)| weight == 0) {
+            return Err("the spawn table has no tile with a weight".to_string());
+        }
+        if let Some(&(score, _)) = self.spawn_table.iter().find(|&&(score, _)| score <= 0) {
+            return Err(format!("invalid spawn score {}", score));
+        }
+        Ok(())
+    }
+}
+
+impl Default for Rules {
+    fn default() -> Rules {
+        Rules {
+            width: 4,
+            height: 4,
+            target: 2048,
+            spawn_table: vec![(2, 9), (4, 1)],
+            spawn_per_move: 1,
+            start_tiles: 2,
+        }
+    }
+}
+
+/// Everything a move changes, kept to undo and redo moves.
+#[derive(Clone)]
+struct Snapshot {
+    cells: Vec<Option<Tile>>,
+    score: i32,
+    moves: u32,
+    status: Status,
+    rng: GameRng,
+}
+
+/// A game in progress, as written to save files.
+#[derive(Clone, Debug, PartialEq, RustcEncodable, RustcDecodable)]
+pub struct SavedGame {
+    pub rules: Rules,
+    pub seed: u64,
+    pub rng: GameRng,
+    pub score: i32,
+    pub moves: u32,
+    pub status: Status,
+    /// (x, y, score) of every tile.
+    pub tiles: Vec<(i32, i32, i32)>,
+    /// Notation of the position the game started from, `None` for a new game.
+    pub start: Option<String>,
+    /// Every move made since the start, so the game can still be replayed.
+    pub directions: Vec<Direction>,
+    /// When the game began, in seconds since the Unix epoch, if noted down.
+    pub started: Option<u64>,
+}
+
+#[derive(Clone)]
+pub struct Game {
+    rules: Rules,
+    /// Tiles indexed by cell, row after row.
+    cells: Vec<Option<Tile>>,
+    score: i32,
+    /// Number of moves which changed the board.
+    moves: u32,
+    status: Status,
+    seed: u64,
+    rng: GameRng,
+    /// Every move which changed the board, the first `moves` of them made
+    /// since the start and the others undone, kept to be redone.
+    directions: Vec<Direction>,
+    /// Position the game started from, `None` for a new game.
+    start: Option<Position>,
+    /// When the game began, in seconds since the Unix epoch, if noted down.
+    started: Option<u64>,
+    /// Whether moves are kept to be undone and replayed, see
+    /// `forget_history`.
+    history: bool,
+    undo_stack: Vec<Snapshot>,
+    redo_stack: Vec<Snapshot>,
+}
+
+impl Game {
+    /// Creates a game played by `rules`. Games with the same rules and
+    /// `seed` spawn the same tiles for the same moves.
+    ///
+    /// `rules` must pass `Rules::check`, which is up to the caller when
+    /// they come from a file or the user.
+    pub fn new(rules: Rules, seed: u64) -> Game {
+        let cells = match rules.width.checked_mul(rules.height) {
+            Some(cells) if cells > 0 => cells as usize,
+            _ => panic!("invalid board size {}x{}, see Rules::check", rules.width, rules.height),
+        };
+
+        let mut game = Game {
+            cells: vec![None; cells],
+            rules,
+            score: 0,
+            moves: 0,
+            status: Status::Playing,
+            seed,
+            rng: GameRng::new(seed),
+            directions: Vec::new(),
+            start: None,
+            started: None,
+            history: true,
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+        };
+        for _ in 0..game.rules.start_tiles {
+            game.generate_tile();
+        }
+        game.update_status();
+        game
+    }
+
+    /// Starts a game from `position`, played by `rules` on a board of the
+    /// size of the position. If a tile is about to spawn, it spawns first.
+    pub fn from_position(rules: Rules, seed: u64, position: &Position) -> Result<Game, String> {
+        let rules = Rules {
+            width: position.width,
+            height: position.height,
+            ..rules
+        };
+        rules.check()?;
+
+        let mut game = Game {
+            cells: vec![None; position.cells.len()],
+            rules,
+            score: position.score,
+            moves: 0,
+            status: Status::Playing,
+            seed,
+            rng: GameRng::new(seed),
+            directions: Vec::new(),
+            start: Some(position.clone()),
+            started: None,
+            history: true,
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+        };
+
+        for y in 0..position.height {
+            for x in 0..position.width {
+                let score = position.score_at(x, y);
+                if score > 0 {
+                    let i = game.index(x, y);
+                    game.cells[i] = Some(Tile::new(score, x, y));
+                }
+                if score >= game.rules.target {
+                    game.status = Status::KeepPlaying;
+                }
+            }
+        }
+
+        if position.turn == Turn::Spawn {
+            for _ in 0..game.rules.spawn_per_move {
+                game.generate_tile();
+            }
+        }
+        game.update_status();
+        Ok(game)
+    }
+
+    /// Current position, if every tile is a power of two which the
+    /// notation can write down.
+    pub fn position(&self) -> Result<Position, String> {
+        let mut cells = Vec::new();
+        for cell in self.cells.iter() {
+            match *cell {
+                Some(ref tile) => match Position::exponent(tile.score) {
+                    Some(exponent) => cells.push(exponent),
+                    None => return Err(format!("tile {} can't be written down", tile.score)),
+                },
+                None => cells.push(0),
+            }
+        }
+
+        Ok(Position {
+            width: self.width(),
+            height: self.height(),
+            cells,
+            score: self.score,
+            turn: Turn::Move,
+        })
+    }
+
+    /// Resumes a saved game. Its undo history is not saved, so it starts
+    /// empty.
+    pub fn from_saved(saved: SavedGame) -> Result<Game, String> {
+        let rules = saved.rules;
+        rules.check()?;
+        if saved.directions.len() != saved.moves as usize {
+            return Err(format!("{} moves were made but {} are recorded", saved.moves, saved.directions.len()));
+        }
+        let start = match saved.start {
+            Some(ref start) => Some(start.parse()?),
+            None => None,
+        };
+
+        let mut cells = vec![None; (rules.width * rules.height) as usize];
+        for &(x, y, score) in saved.tiles.iter() {
+            if x < 0 || x >= rules.width || y < 0 || y >= rules.height {
+                return Err(format!("tile ({}, {}) is outside the board", x, y));
+            }
+            if score <= 0 {
+                return Err(format!("tile ({}, {}) has an invalid score {}", x, y, score));
+            }
+
+            let i = (y * rules.width + x) as usize;
+            if cells[i].is_some() {
+                return Err(format!("more than one tile at ({}, {})", x, y));
+            }
+            cells[i] = Some(Tile::new(score, x, y));
+        }
+
+        Ok(Game {
+            rules,
+            cells,
+            score: saved.score,
+            moves: saved.moves,
+            status: saved.status,
+            seed: saved.seed,
+            rng: saved.rng,
+            directions: saved.directions,
+            start,
+            started: saved.started,
+            history: true,
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+        })
+    }
+
+    pub fn save(&self) -> SavedGame {
+        SavedGame {
+            rules: self.rules.clone(),
+            seed: self.seed,
+            rng: self.rng.clone(),
+            score: self.score,
+            moves: self.moves,
+            status: self.status,
+            tiles: self.tiles().map(|tile| (tile.tile_x, tile.tile_y, tile.score)).collect(),
+            start: self.start.as_ref().map(|start| start.to_string()),
+            directions: self.directions().to_vec(),
+            started: self.started,
+        }
+    }
+
+    /// Replay of the moves made since the start.
+    pub fn replay(&self) -> Replay {
+        Replay {
+            rules: self.rules.clone(),
+            seed: self.seed,
+            start: self.start.as_ref().map(|start| start.to_string()),
+            directions: self.directions().to_vec(),
+        }
+    }
+
+    pub fn rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    pub fn width(&self) -> i32 {
+        self.rules.width
+    }
+
+    pub fn height(&self) -> i32 {
+        self.rules.height
+    }
+
+    pub fn score(&self) -> i32 {
+        self.score
+    }
+
+    pub fn moves(&self) -> u32 {
+        self.moves
+    }
+
+    /// Moves which changed the board since the start, undone ones left out.
+    pub fn directions(&self) -> &[Direction] {
+        // nothing is kept once the history is forgotten
+        let kept = (self.moves as usize).min(self.directions.len());
+        &self.directions[..kept]
+    }
+
+    pub fn tiles(&self) -> impl Iterator<Item = &Tile> {
+        self.cells.iter().filter_map(|cell| cell.as_ref())
+    }
+
+    pub fn tile_count(&self) -> usize {
+        self.tiles().count()
+    }
+
+    /// Returns the tile at (x, y), `None` for empty cells and cells outside
+    /// the board.
+    pub fn get_tile(&self, x: i32, y: i32) -> Option<&Tile> {
+        if !self.contains(x, y) {
+            return None;
+        }
+
+        self.cells[self.index(x, y)].as_ref()
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Position the game started from, `None` for a new game.
+    pub fn start(&self) -> Option<&Position> {
+        self.start.as_ref()
+    }
+
+    pub fn started(&self) -> Option<u64> {
+        self.started
+    }
+
+    /// Notes down when the game began, in seconds since the Unix epoch.
+    pub fn set_started(&mut self, started: u64) {
+        self.started = Some(started);
+    }
+
+    pub fn target(&self) -> i32 {
+        self.rules.target
+    }
+
+    pub fn status(&self) -> Status {
+        self.status
+    }
+
+    /// Goes on with the game after it has been won.
+    pub fn keep_playing(&mut self) {
+        if self.status == Status::Won {
+            self.status = Status::KeepPlaying;
+            self.update_status();
+        }
+    }
+
+    /// Whether any move would change the board.
+    pub fn can_move(&self) -> bool {
+        if self.tile_count() < self.cells.len() {
+            return true;
+        }
+
+        for tile in self.tiles() {
+            for &(step_x, step_y) in [(1, 0), (0, 1)].iter() {
+                if let Some(neighbour) = self.get_tile(tile.tile_x + step_x, tile.tile_y + step_y) {
+                    if can_merge(neighbour.score, tile.score) {
+                        return true;
+                    }
+                }
+            }
+        }
+
+        false
+    }
+
+    /// Whether moving towards `direction` would change the board.
+    pub fn can_move_towards(&self, direction: Direction) -> bool {
+        if self.status == Status::Won {
+            return false;
+        }
+
+        for line in self.lines(direction) {
+            let mut previous = None;
+            let mut gap = false;
+
+            for &(x, y) in line.iter() {
+                match self.get_tile(x, y) {
+                    Some(tile) => {
+                        if gap || previous.is_some_and(|previous| can_merge(previous, tile.score)) {
+                            return true;
+                        }
+                        previous = Some(tile.score);
+                    },
+                    None => gap = true,
+                }
+            }
+        }
+
+        false
+    }
+
+    /// Directions which would change the board.
+    pub fn legal_moves(&self) -> Vec<Direction> {
+        Direction::all().iter().cloned().filter(|&direction| self.can_move_towards(direction)).collect()
+    }
+
+    /// Spawns a tile picked from the spawn table on a random empty cell,
+    /// if there is any.
+    pub fn generate_tile(&mut self) -> Option<TileSpawn> {
+        let empty: Vec<usize> = (0..self.cells.len()).filter(|&i| self.cells[i].is_none()).collect();
+
+        if empty.is_empty() {
+            return None;
+        }
+
+        let i = empty[self.rng.gen_range(0, empty.len())];
+        let (x, y) = (i as i32 % self.width(), i as i32 / self.width());
+
+        let total_weight: u32 = self.rules.spawn_table.iter().map(|&(_, weight)| weight).sum();
+        let mut pick = self.rng.gen_range(0, total_weight);
+        let mut score = 0;
+        for &(value, weight) in self.rules.spawn_table.iter() {
+            if pick < weight {
+                score = value;
+                break;
+            }
+            pick -= weight;
+        }
+
+        self.cells[i] = Some(Tile::new(score, x, y));
+        Some(TileSpawn {
+            position: (x, y),
+            score,
+        })
+    }
+
+    /// Stops keeping the moves made, to undo or replay them, which players
+    /// without a window making millions of moves have no use for. The game
+    /// can't be undone, saved or replayed from then on.
+    pub fn forget_history(&mut self) {
+        self.history = false;
+        self.directions = Vec::new();
+        self.undo_stack = Vec::new();
+        self.redo_stack = Vec::new();
+    }
+
+    /// Copy of the game without its history, see `forget_history`, which is
+    /// much cheaper than `clone` to try moves on.
+    pub fn copy_without_history(&self) -> Game {
+        Game {
+            rules: self.rules.clone(),
+            cells: self.cells.clone(),
+            score: self.score,
+            moves: self.moves,
+            status: self.status,
+            seed: self.seed,
+            rng: self.rng.clone(),
+            directions: Vec::new(),
+            start: self.start.clone(),
+            started: self.started,
+            history: false,
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    /// Takes back the last move.
+    pub fn undo(&mut self) {
+        if let Some(snapshot) = self.undo_stack.pop() {
+            let current = self.snapshot();
+            self.redo_stack.push(current);
+            self.restore(snapshot);
+        }
+    }
+
+    /// Makes the last undone move again, with the same tile spawned.
+    pub fn redo(&mut self) {
+        if let Some(snapshot) = self.redo_stack.pop() {
+            let current = self.snapshot();
+            self.undo_stack.push(current);
+            self.restore(snapshot);
+        }
+    }
+
+    /// Slides every tile as far as possible towards `direction`, merging
+    /// equal tiles which run into each other, then spawns a tile if
+    /// anything moved.
+    pub fn move_tiles(&mut self, direction: Direction) -> MoveResult {
+        if self.status == Status::Won {
+            return MoveResult::default();
+        }
+
+        let score_before = self.score;
+        let before = if self.history { Some(self.snapshot()) } else { None };
+        self.start_move();
+
+        let mut changed = false;
+        for line in self.lines(direction) {
+            if self.move_line(&line) {
+                changed = true;
+            }
+        }
+
+        let mut result = MoveResult::default();
+
+        if changed {
+            if let Some(before) = before {
+                self.directions.truncate(self.moves as usize);
+                self.directions.push(direction);
+                self.undo_stack.push(before);
+                self.redo_stack.clear();
+            }
+            self.moves += 1;
+            result = self.move_result(score_before);
+            for _ in 0..self.rules.spawn_per_move {
+                if let Some(spawn) = self.generate_tile() {
+                    result.spawned.push(spawn);
+                }
+            }
+        }
+
+        self.update_status();
+        result
+    }
+
+    /// Cells of every line along `direction`, each starting from the edge
+    /// tiles move towards.
+    fn lines(&self, direction: Direction) -> Vec<Vec<(i32, i32)>> {
+        let (step_x, step_y) = direction.step();
+        let mut lines = Vec::new();
+
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                if self.contains(x + step_x, y + step_y) {
+                    continue;
+                }
+
+                let mut line = Vec::new();
+                let (mut cx, mut cy) = (x, y);
+                while self.contains(cx, cy) {
+                    line.push((cx, cy));
+                    cx -= step_x;
+                    cy -= step_y;
+                }
+                lines.push(line);
+            }
+        }
+
+        lines
+    }
+
+    /// Packs the tiles of a line towards its first cell, merging each tile
+    /// into the one before it when they are equal and that one isn't the
+    /// result of a merge already. Returns whether anything moved.
+    fn move_line(&mut self, line: &[(i32, i32)]) -> bool {
+        let mut changed = false;
+        let mut next = 0;
+
+        for &(x, y) in line.iter() {
+            let score = match self.get_tile(x, y) {
+                Some(tile) => tile.score,
+                None => continue,
+            };
+
+            if next > 0 {
+                let (px, py) = line[next - 1];
+                let previous = self.get_tile(px, py).unwrap();
+                if can_merge(previous.score, score) && previous.merged_from.is_none() {
+                    self.merge_tile(x, y, px, py);
+                    changed = true;
+                    continue;
+                }
+            }
+
+            let (nx, ny) = line[next];
+            if (nx, ny) != (x, y) {
+                self.move_tile(x, y, nx, ny);
+                changed = true;
+            }
+            next += 1;
+        }
+
+        changed
+    }
+
+    /// Collects what the current move did from where the tiles say they
+    /// came from.
+    fn move_result(&self, score_before: i32) -> MoveResult {
+        let mut result = MoveResult {
+            score_gained: self.score - score_before,
+            changed: true,
+            ..MoveResult::default()
+        };
+
+        for tile in self.tiles() {
+            let position = (tile.tile_x, tile.tile_y);
+
+            if let Some(sources) = tile.merged_from {
+                result.merged.push(TileMerge {
+                    sources,
+                    position,
+                    score: tile.score,
+                });
+            } else if let Some(from) = tile.origin {
+                if from != position {
+                    result.moved.push(TileMove {
+                        from,
+                        to: position,
+                        score: tile.score,
+                    });
+                }
+            }
+        }
+
+        result
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            cells: self.cells.clone(),
+            score: self.score,
+            moves: self.moves,
+            status: self.status,
+            rng: self.rng.clone(),
+        }
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.cells = snapshot.cells;
+        self.score = snapshot.score;
+        self.moves = snapshot.moves;
+        self.status = snapshot.status;
+        self.rng = snapshot.rng;
+    }
+
+    fn update_status(&mut self) {
+        if self.status != Status::Won && !self.can_move() {
+            self.status = Status::GameOver;
+        }
+    }
+
+    /// Remembers where every tile starts from before a move.
+    fn start_move(&mut self) {
+        for tile in self.cells.iter_mut().filter_map(|cell| cell.as_mut()) {
+            tile.origin = Some((tile.tile_x, tile.tile_y));
+            tile.merged_from = None;
+        }
+    }
+
+    fn move_tile(&mut self, sx: i32, sy: i32, dx: i32, dy: i32) {
+        let (s, d) = (self.index(sx, sy), self.index(dx, dy));
+        let mut tile = self.cells[s].take().unwrap();
+        tile.tile_x = dx;
+        tile.tile_y = dy;
+        self.cells[d] = Some(tile);
+    }
+
+    fn merge_tile(&mut self, sx: i32, sy: i32, dx: i32, dy: i32) {
+        let (s, d) = (self.index(sx, sy), self.index(dx, dy));
+        let source = self.cells[s].take().unwrap();
+
+        let score = {
+            let dest = self.cells[d].as_mut().unwrap();
+            dest.score += source.score;
+            dest.merged_from = Some([dest.origin.unwrap(), source.origin.unwrap()]);
+            dest.score
+        };
+
+        self.add_score(score);
+
+        if score >= self.rules.target && self.status == Status::Playing {
+            self.status = Status::Won;
+        }
+    }
+
+    fn contains(&self, x: i32, y: i32) -> bool {
+        x >= 0 && x < self.width() && y >= 0 && y < self.height()
+    }
+
+    fn index(&self, x: i32, y: i32) -> usize {
+        (y * self.width() + x) as usize
+    }
+
+    // the score stops at the highest one rather than overflowing
+    fn add_score(&mut self, score: i32) {
+        self.score = self.score.saturating_add(score);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A game from the position written `s`, with no tile spawning unless
+    /// `spawn_table` says which one does.
+    fn game(s: &str, target: i32, spawn_table: Option<Vec<(i32, u32)>>) -> Game {
+        let rules = Rules {
+            target,
+            spawn_per_move: if spawn_table.is_some() { 1 } else { 0 },
+            spawn_table: spawn_table.unwrap_or_else(|| Rules::default().spawn_table),
+            ..Rules::default()
+        };
+        Game::from_position(rules, 0, &s.parse().unwrap()).unwrap()
+    }
+
+    fn board(game: &Game) -> String {
+        game.position().unwrap().to_string()
+    }
+
+    #[test]
+    fn checks_board_size() {
+        let rules = |width, height| Rules { width, height, ..Rules::default() };
+        assert_eq!(rules(1, MAX_SIZE).check(), Ok(()));
+        assert!(rules(0, 4).check().is_err());
+        assert!(rules(4, -1).check().is_err());
+        assert!(rules(MAX_SIZE + 1, 4).check().is_err());
+        assert!(rules(100000, 100000).check().is_err());
+    }
+
+    #[test]
+    fn tells_tiles_apart_by_exponent() {
+        let exponents: Vec<u8> = [0, 1, 2, 3, 4, 2048, 3000, i32::MAX].iter().map(|&score| exponent(score)).collect();
+        assert_eq!(exponents, [0, 1, 1, 1, 2, 11, 11, 30]);
+    }
+
+    #[test]
+    fn checks_spawn_table() {
+        assert_eq!(Rules::default().check(), Ok(()));
+
+        let rules = |spawn_table| Rules { spawn_table, ..Rules::default() };
+        assert_eq!(rules(vec![(2, 0), (4, 0)]).check(), Err("the spawn table has no tile with a weight".to_string()));
+        assert_eq!(rules(vec![(2, 9), (0, 1)]).check(), Err("invalid spawn score 0".to_string()));
+        assert_eq!(rules(vec![(-2, 1)]).check(), Err("invalid spawn score -2".to_string()));
+    }
+
+    #[test]
+    fn merges_each_tile_once() {
+        let mut row = game("4x1 1111 0 m", 2048, None);
+        assert_eq!(row.move_tiles(Direction::Left).score_gained, 8);
+        assert_eq!(board(&row), "4x1 2200 8 m");
+
+        let mut row = game("3x1 112 0 m", 2048, None);
+        assert_eq!(row.move_tiles(Direction::Left).score_gained, 4);
+        assert_eq!(board(&row), "3x1 220 4 m");
+
+        let mut row = game("3x1 112 0 m", 2048, None);
+        assert_eq!(row.move_tiles(Direction::Right).score_gained, 4);
+        assert_eq!(board(&row), "3x1 022 4 m");
+    }
+
+    #[test]
+    fn stops_before_overflowing() {
+        // two 2^30 would make 2^31, which doesn't fit
+        let mut highest = game("2x1 uu 0 m", 2048, None);
+        assert!(highest.legal_moves().is_empty());
+        assert!(!highest.move_tiles(Direction::Left).changed);
+        assert_eq!(highest.status(), Status::GameOver);
+
+        let mut row = game("2x1 tt 0 m", 2048, None);
+        assert_eq!(row.move_tiles(Direction::Left).score_gained, 1 << 30);
+        assert_eq!(board(&row), "2x1 u0 1073741824 m");
+
+        let mut row = game("2x1 11 2147483647 m", 2048, None);
+        assert_eq!(row.move_tiles(Direction::Left).score_gained, 0);
+        assert_eq!(row.score(), i32::MAX);
+    }
+
+    #[test]
+    fn ends_when_nothing_moves() {
+        let full = game("2x2 12/21 0 m", 2048, None);
+        assert!(!full.can_move());
+        assert!(full.legal_moves().is_empty());
+        assert_eq!(full.status(), Status::GameOver);
+
+        let mergeable = game("2x2 12/11 0 m", 2048, None);
+        assert!(mergeable.can_move());
+        assert_eq!(mergeable.status(), Status::Playing);
+
+        // the spawned 8 fills the board next to the merged 4
+        let mut last = game("2x1 11 0 m", 2048, Some(vec![(8, 1)]));
+        assert!(last.move_tiles(Direction::Left).changed);
+        assert_eq!(board(&last), "2x1 23 4 m");
+        assert_eq!(last.status(), Status::GameOver);
+    }
+
+    #[test]
+    fn wins_once() {
+        // a target which isn't a power of two is won by the next tile past it
+        let mut past_target = game("2x1 22 0 m", 6, None);
+        past_target.move_tiles(Direction::Left);
+        assert_eq!(past_target.status(), Status::Won);
+
+        let mut game = game("4x2 2200/1111 0 m", 8, None);
+        game.move_tiles(Direction::Left);
+        assert_eq!(game.status(), Status::Won);
+
+        assert!(!game.move_tiles(Direction::Left).changed);
+        assert_eq!(board(&game), "4x2 3000/2200 16 m");
+
+        game.keep_playing();
+        assert_eq!(game.status(), Status::KeepPlaying);
+
+        // another 8 doesn't win again
+        assert!(game.move_tiles(Direction::Left).changed);
+        assert_eq!(board(&game), "4x2 3000/3000 24 m");
+        assert_eq!(game.status(), Status::KeepPlaying);
+    }
+
+    #[test]
+    fn resumes_saved_games() {
+        let mut game = Game::new(Rules::default(), 3);
+        let direction = game.legal_moves()[0];
+        game.move_tiles(direction);
+        let saved = game.save();
+        assert_eq!(Game::from_saved(saved.clone()).unwrap().save(), saved);
+
+        let rejected = |change: &dyn Fn(&mut SavedGame)| {
+            let mut bad = saved.clone();
+            change(&mut bad);
+            Game::from_saved(bad).err().unwrap()
+        };
+        assert_eq!(rejected(&|bad| bad.rules.width = 0), "invalid board size 0x4, boards go from 1x1 to 64x64");
+        assert_eq!(rejected(&|bad| bad.directions.clear()), "1 moves were made but 0 are recorded");
+        assert_eq!(rejected(&|bad| bad.tiles.push((4, 0, 2))), "tile (4, 0) is outside the board");
+        assert_eq!(rejected(&|bad| bad.tiles.push((0, -1, 2))), "tile (0, -1) is outside the board");
+
+        let (x, y, score) = saved.tiles[0];
+        assert_eq!(rejected(&|bad| bad.tiles[0].2 = 0), format!("tile ({}, {}) has an invalid score 0", x, y));
+        assert_eq!(rejected(&|bad| bad.tiles.push((x, y, score))), format!("more than one tile at ({}, {})", x, y));
+        assert!(!rejected(&|bad| bad.start = Some("4x4 junk".to_string())).is_empty());
+    }
+
+    #[test]
+    fn redoes_the_same_spawn() {
+        let mut game = Game::new(Rules::default(), 7);
+        let before = game.save();
+        let direction = game.legal_moves()[0];
+        game.move_tiles(direction);
+        let after = game.save();
+
+        game.undo();
+        assert_eq!(game.save(), before);
+        game.redo();
+        assert_eq!(game.save(), after);
+
+        // moving again after an undo draws the same tile too
+        game.undo();
+        game.move_tiles(direction);
+        assert_eq!(game.save(), after);
+    }
+
+    #[test]
+    fn plays_the_same_without_history() {
+        let mut game = Game::new(Rules::default(), 5);
+        let mut copy = game.copy_without_history();
+        let mut forgetful = Game::new(Rules::default(), 5);
+        forgetful.forget_history();
+
+        for i in 0..100 {
+            let direction = Direction::all()[i % 4];
+            let result = game.move_tiles(direction);
+            assert_eq!(copy.move_tiles(direction), result);
+            assert_eq!(forgetful.move_tiles(direction), result);
+        }
+        assert_eq!(copy.position(), game.position());
+        assert_eq!(forgetful.position(), game.position());
+        assert_eq!(copy.moves(), game.moves());
+        assert!(copy.directions().is_empty() && !copy.can_undo());
+    }
+
+    #[test]
+    fn same_seed_same_game() {
+        let mut a = Game::new(Rules::default(), 42);
+        let mut b = Game::new(Rules::default(), 42);
+
+        for i in 0..200 {
+            let direction = Direction::all()[i % 4];
+            a.move_tiles(direction);
+            b.move_tiles(direction);
+        }
+        assert!(a.moves() > 0);
+        assert_eq!(a.save(), b.save());
+    }
+}
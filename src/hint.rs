@@ -0,0 +1,93 @@
+//! Suggested move drawn over the board: an arrow towards the best direction
+//! and the evaluation of every move next to the edge it moves towards.
+
+use piston_window::*;
+use opengl_graphics::GlGraphics;
+use rust_2048::ai::Ai;
+use rust_2048::game::{ Direction, Game };
+use rust_2048::theme::Theme;
+use number_renderer::NumberRenderer;
+
+static HINT_ALPHA: f32 = 0.3;
+static ARROW_ALPHA: f32 = 0.8;
+/// Width of the evaluations, and their distance from the edges of the board.
+static EVALUATION_WIDTH: f64 = 64.0;
+static EVALUATION_MARGIN: f64 = 20.0;
+
+pub struct Hint {
+    best: Direction,
+    /// Evaluation of every move, `None` for the ones which don't change
+    /// the board.
+    evaluations: Vec<(Direction, Option<f64>)>,
+}
+
+impl Hint {
+    /// Searches the best move of `game`, `None` if no move is left.
+    pub fn new(ai: &Ai, game: &Game) -> Option<Hint> {
+        let evaluations = ai.evaluate_moves(game);
+
+        Ai::best_of(&evaluations).map(|best| Hint {
+            best,
+            evaluations,
+        })
+    }
+
+    /// Draws the hint over the board filling `rect`.
+    pub fn render(&self, theme: &Theme, number_renderer: &NumberRenderer, rect: [f64; 4],
+                  c: &Context, gl: &mut GlGraphics) {
+        let bg = theme.window_background_color;
+        Rectangle::new([bg[0], bg[1], bg[2], HINT_ALPHA])
+            .draw(rect,
+                  &DrawState::default(),
+                  c.transform,
+                  gl);
+
+        let (center_x, center_y) = (rect[0] + rect[2] / 2.0, rect[1] + rect[3] / 2.0);
+        self.render_arrow(theme, rect[2].min(rect[3]) / 3.0, c.trans(center_x, center_y), gl);
+
+        for &(direction, value) in self.evaluations.iter() {
+            let value = match value {
+                Some(value) => value.round() as i32,
+                None => continue,
+            };
+
+            let (step_x, step_y) = direction.step();
+            let x = center_x + step_x as f64 * (rect[2] / 2.0 - EVALUATION_MARGIN);
+            let y = center_y + step_y as f64 * (rect[3] / 2.0 - EVALUATION_MARGIN);
+            let color = if direction == self.best {
+                theme.button_color
+            } else {
+                theme.text_dark_color
+            };
+
+            number_renderer.render_signed(value, x, y, EVALUATION_WIDTH, color, c, gl);
+        }
+    }
+
+    /// Draws an arrow `length` long pointing from the origin of `c` towards
+    /// the best direction.
+    fn render_arrow(&self, theme: &Theme, length: f64, c: Context, gl: &mut GlGraphics) {
+        let angle = match self.best {
+            Direction::Right => 0.0,
+            Direction::Down => 90.0,
+            Direction::Left => 180.0,
+            Direction::Up => 270.0,
+        };
+        let transform = c.rot_deg(angle).transform;
+
+        let color = theme.button_color;
+        let color = [color[0], color[1], color[2], ARROW_ALPHA];
+        let (half, shaft, head) = (length / 2.0, length / 8.0, length / 3.0);
+
+        Rectangle::new(color)
+            .draw([-half, -shaft / 2.0, length - head, shaft],
+                  &DrawState::default(),
+                  transform,
+                  gl);
+        Polygon::new(color)
+            .draw(&[[half - head, -head / 1.5], [half, 0.0], [half - head, head / 1.5]],
+                  &DrawState::default(),
+                  transform,
+                  gl);
+    }
+}
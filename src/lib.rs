@@ -0,0 +1,15 @@
+extern crate rand;
+extern crate rustc_serialize;
+
+pub mod ai;
+pub mod animation;
+pub mod bitboard;
+pub mod environment;
+pub mod game;
+pub mod notation;
+pub mod protocol;
+pub mod replay;
+pub mod rng;
+pub mod settings;
+pub mod simulation;
+pub mod theme;
@@ -3,19 +3,63 @@ extern crate rand;
 extern crate piston_window;
 extern crate opengl_graphics;
 extern crate sdl2_window;
+extern crate rust_2048;
 
+use std::path::Path;
 use piston_window::*;
 use sdl2_window::Sdl2Window;
+use rust_2048::protocol;
+use rust_2048::replay::Replay;
+use rust_2048::settings::Settings;
 
 mod app;
+mod best_score;
 mod board;
+mod hint;
 mod number_renderer;
-mod settings;
+mod replay_player;
+mod score_popup;
+mod storage;
 mod tile;
 
+/// Reads the value following `name` on the command line.
+fn arg_value(name: &str) -> Option<String> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == name {
+            return args.next();
+        }
+    }
+    None
+}
+
+fn has_flag(name: &str) -> bool {
+    std::env::args().skip(1).any(|arg| arg == name)
+}
+
+/// Reads the replay file given by `--replay <path>`.
+fn replay_from_args() -> Option<Replay> {
+    let path = arg_value("--replay")?;
+
+    match storage::read(Path::new(&path)) {
+        Ok(replay) => Some(replay),
+        Err(e) => {
+            println!("WARNING: Failed to load replay {}: {}", path, e);
+            None
+        },
+    }
+}
+
 fn main() {
 	use opengl_graphics::GlGraphics;	
-    let settings = settings::Settings::load();
+    let mut settings = Settings::load();
+    settings.read_seed_arg(std::env::args().skip(1));
+
+    if has_flag("--protocol") {
+        let stdin = std::io::stdin();
+        protocol::run(settings.rules(), settings.seed, stdin.lock(), &mut std::io::stdout()).unwrap();
+        return;
+    }
 
 	let (width, height) = (settings.window_size[0], 
 	                       settings.window_size[1]);
@@ -31,6 +75,17 @@ fn main() {
 
     app.load();
 
+    if let Some(notation) = arg_value("--position") {
+        app.play_position(&notation);
+    }
+
+    if let Some(replay) = replay_from_args() {
+        app.play_replay(replay);
+    }
+
+    let mut title = app.title();
+    window.set_title(title.clone());
+
     let mut gl = GlGraphics::new(OpenGL::V3_2);
 
     while let Some(e) = window.next() {
@@ -44,8 +99,19 @@ fn main() {
            app.update(args);
         }
 
+        if let Some(ref args) = e.mouse_cursor_args() {
+            app.mouse_move(args);
+        }
+
         if let Some(ref args) = e.press_args() {
             app.key_press(args);
         }
+
+        if app.title() != title {
+            title = app.title();
+            window.set_title(title.clone());
+        }
     }
+
+    app.save_game();
 }
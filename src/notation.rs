@@ -0,0 +1,198 @@
+//! Text notation for board positions, in the spirit of FEN in chess.
+//!
+//! A position is written as four fields separated by spaces:
+//!
+//! ```text
+//! 4x4 0100/0000/0021/000b 2160 m
+//! ```
+//!
+//! * the board size, width by height
+//! * the rows from top to bottom, separated by `/`, each cell written as
+//!   the exponent of its tile in base 36 (`1` for 2, `b` for 2048) and `0`
+//!   when empty
+//! * the score
+//! * who plays next: `m` when the player is to move, `s` when a tile is
+//!   about to spawn
+
+use std::fmt;
+use std::str::FromStr;
+use game;
+
+/// Highest exponent of a tile, the highest power of two a score can hold.
+pub static MAX_EXPONENT: u8 = 30;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, RustcEncodable, RustcDecodable)]
+pub enum Turn {
+    /// The player is to move.
+    Move,
+    /// A tile is about to spawn after a move.
+    Spawn,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, RustcEncodable, RustcDecodable)]
+pub struct Position {
+    pub width: i32,
+    pub height: i32,
+    /// Exponent of the tile in every cell, row after row, 0 for empty cells.
+    pub cells: Vec<u8>,
+    pub score: i32,
+    pub turn: Turn,
+}
+
+impl Position {
+    /// Exponent of `score` if it can be written down, that is if it is a
+    /// power of two no higher than 2^`MAX_EXPONENT`.
+    pub fn exponent(score: i32) -> Option<u8> {
+        if score < 2 || score.count_ones() != 1 {
+            return None;
+        }
+
+        let exponent = game::exponent(score);
+        if exponent <= MAX_EXPONENT {
+            Some(exponent)
+        } else {
+            None
+        }
+    }
+
+    /// Score of the tile in cell (x, y), 0 for empty cells.
+    pub fn score_at(&self, x: i32, y: i32) -> i32 {
+        match self.cells[(y * self.width + x) as usize] {
+            0 => 0,
+            exponent => 1 << exponent,
+        }
+    }
+}
+
+/// Parses a board size written `<width>x<height>`.
+pub fn parse_size(s: &str) -> Option<(i32, i32)> {
+    let mut parts = s.split('x');
+    let width: i32 = parts.next()?.parse().ok()?;
+    let height: i32 = parts.next()?.parse().ok()?;
+
+    if parts.next().is_some() || width <= 0 || height <= 0 {
+        return None;
+    }
+    Some((width, height))
+}
+
+impl FromStr for Position {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Position, String> {
+        let fields: Vec<&str> = s.split_whitespace().collect();
+        if fields.len() != 4 {
+            return Err(format!("expected 4 fields but found {}", fields.len()));
+        }
+
+        let (width, height) = parse_size(fields[0])
+            .ok_or_else(|| format!("invalid board size {}", fields[0]))?;
+
+        let rows: Vec<&str> = fields[1].split('/').collect();
+        if rows.len() != height as usize {
+            return Err(format!("expected {} rows but found {}", height, rows.len()));
+        }
+
+        let mut cells = Vec::new();
+        for (y, row) in rows.iter().enumerate() {
+            if row.chars().count() != width as usize {
+                return Err(format!("expected {} cells in row {} but found {}", width, y + 1, row.chars().count()));
+            }
+
+            for c in row.chars() {
+                match c.to_digit(36) {
+                    Some(exponent) if exponent <= u32::from(MAX_EXPONENT) => cells.push(exponent as u8),
+                    _ => return Err(format!("invalid cell '{}' in row {}", c, y + 1)),
+                }
+            }
+        }
+
+        let score = match fields[2].parse() {
+            Ok(score) if score >= 0 => score,
+            _ => return Err(format!("invalid score {}", fields[2])),
+        };
+
+        let turn = match fields[3] {
+            "m" => Turn::Move,
+            "s" => Turn::Spawn,
+            turn => return Err(format!("invalid turn {}, expected m or s", turn)),
+        };
+
+        Ok(Position {
+            width,
+            height,
+            cells,
+            score,
+            turn,
+        })
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}x{} ", self.width, self.height)?;
+
+        for (i, row) in self.cells.chunks(self.width as usize).enumerate() {
+            if i > 0 {
+                write!(f, "/")?;
+            }
+            for &exponent in row.iter() {
+                write!(f, "{}", ::std::char::from_digit(u32::from(exponent), 36).unwrap_or('?'))?;
+            }
+        }
+
+        let turn = match self.turn {
+            Turn::Move => "m",
+            Turn::Spawn => "s",
+        };
+        write!(f, " {} {}", self.score, turn)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use game::{ Game, Rules };
+    use super::*;
+
+    fn error(s: &str) -> String {
+        s.parse::<Position>().unwrap_err()
+    }
+
+    #[test]
+    fn round_trips() {
+        let s = "4x4 0100/0000/0021/000b 2160 m";
+        let position: Position = s.parse().unwrap();
+
+        assert_eq!(position.width, 4);
+        assert_eq!(position.height, 4);
+        assert_eq!(position.score_at(1, 0), 2);
+        assert_eq!(position.score_at(2, 2), 4);
+        assert_eq!(position.score_at(3, 3), 2048);
+        assert_eq!(position.score, 2160);
+        assert_eq!(position.turn, Turn::Move);
+        assert_eq!(position.to_string(), s);
+    }
+
+    #[test]
+    fn rejects_malformed_positions() {
+        assert_eq!(error("4x4 0000/0000/0000/0000 0"), "expected 4 fields but found 3");
+        assert_eq!(error("4x0 0000 0 m"), "invalid board size 4x0");
+        assert_eq!(error("4x4 0000/0000/0000 0 m"), "expected 4 rows but found 3");
+        assert_eq!(error("4x4 0000/000/0000/0000 0 m"), "expected 4 cells in row 2 but found 3");
+        assert_eq!(error("4x4 0000/0000/00z0/0000 0 m"), "invalid cell 'z' in row 3");
+        assert_eq!(error("4x4 0000/0000/0000/0000 -4 m"), "invalid score -4");
+        assert_eq!(error("4x4 0000/0000/0000/0000 ten m"), "invalid score ten");
+        assert_eq!(error("4x4 0000/0000/0000/0000 0 x"), "invalid turn x, expected m or s");
+    }
+
+    #[test]
+    fn spawns_when_a_tile_is_due() {
+        let position: Position = "4x4 1000/0000/0000/0000 0 s".parse().unwrap();
+        let game = Game::from_position(Rules::default(), 0, &position).unwrap();
+
+        let after = game.position().unwrap();
+        assert_eq!(after.cells[0], 1);
+        assert_eq!(after.cells.iter().filter(|&&exponent| exponent > 0).count(), 2);
+        assert_eq!(after.turn, Turn::Move);
+    }
+}
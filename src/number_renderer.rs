@@ -22,6 +22,12 @@ impl NumberRenderer {
 
     pub fn render(&self, number: u32, center_x: f64, center_y: f64, max_width: f64,
                   color: [f32; 3], c: &Context, gl: &mut GlGraphics) {
+        self.render_rgba(number, center_x, center_y, max_width, [color[0], color[1], color[2], 1.0], c, gl);
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn render_rgba(&self, number: u32, center_x: f64, center_y: f64, max_width: f64,
+                   color: [f32; 4], c: &Context, gl: &mut GlGraphics) {
         let digits = number_to_digits(number);
         let total_width = DIGITS_WIDTH * digits.len() as f64;
         let total_width = if total_width > max_width {
@@ -35,7 +41,7 @@ impl NumberRenderer {
         let y = center_y - height / 2.0;
 
         for digit in digits.iter() {
-            Image::new_color([color[0], color[1], color[2], 1.0])
+            Image::new_color(color)
                 .src_rect([(*digit * DIGITS_WIDTH as u32) as f64, 0 as f64, DIGITS_WIDTH as f64, DIGITS_HEIGHT as f64])
                 .rect([x, y, width, height])
                 .draw(&self.image,
@@ -46,6 +52,46 @@ impl NumberRenderer {
             x += width;
         }
     }
+
+    /// Renders `number` with a leading minus sign when it is negative.
+    #[allow(clippy::too_many_arguments)]
+    pub fn render_signed(&self, number: i32, center_x: f64, center_y: f64, max_width: f64,
+                         color: [f32; 3], c: &Context, gl: &mut GlGraphics) {
+        if number >= 0 {
+            self.render(number as u32, center_x, center_y, max_width, color, c, gl);
+            return;
+        }
+
+        self.render_with_sign(number, center_x, center_y, max_width, [color[0], color[1], color[2], 1.0], c, gl);
+    }
+
+    /// Renders `number` with a leading plus or minus sign, even when it is
+    /// positive.
+    #[allow(clippy::too_many_arguments)]
+    pub fn render_with_sign(&self, number: i32, center_x: f64, center_y: f64, max_width: f64,
+                            color: [f32; 4], c: &Context, gl: &mut GlGraphics) {
+        // the sign takes the width of a digit
+        let count = number_to_digits(number.unsigned_abs()).len() as f64 + 1.0;
+        let total_width = (DIGITS_WIDTH * count).min(max_width);
+        let width = total_width / count;
+        let height = width / DIGITS_WIDTH * DIGITS_HEIGHT;
+        let x = center_x - total_width / 2.0;
+
+        Rectangle::new(color)
+            .draw([x + width * 0.2, center_y - height * 0.06, width * 0.6, height * 0.12],
+                  &DrawState::default(),
+                  c.transform,
+                  gl);
+        if number >= 0 {
+            Rectangle::new(color)
+                .draw([x + width * 0.5 - height * 0.06, center_y - width * 0.3, height * 0.12, width * 0.6],
+                      &DrawState::default(),
+                      c.transform,
+                      gl);
+        }
+
+        self.render_rgba(number.unsigned_abs(), center_x + width / 2.0, center_y, total_width - width, color, c, gl);
+    }
 }
 
 fn number_to_digits(number: u32) -> Vec<u32> {
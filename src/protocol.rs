@@ -0,0 +1,199 @@
+//! Line-based text protocol, like UCI for chess engines, so that bots
+//! written in any language can play the real rules over stdin/stdout.
+//!
+//! Every command gets exactly one line in reply, `error <reason>` if it
+//! can't be carried out:
+//!
+//! ```text
+//! new [seed <n>] [size <width>x<height>]  ok <seed>
+//! position <notation>                     ok
+//! board                                   board <notation>
+//! score                                   score <score>
+//! moves                                   moves [up] [down] [left] [right]
+//! move <up|down|left|right>               moved <score gained>
+//! continue                                ok
+//! status                                  status <playing|won|keep-playing|game-over>
+//! over                                    over <yes|no>
+//! quit
+//! ```
+//!
+//! Positions are written in the notation of the `notation` module. Only
+//! legal moves, the ones listed by `moves`, can be made. Once the target
+//! tile is reached, `continue` goes on with the game. Boards go up to
+//! `game::MAX_SIZE` cells on either side.
+
+use std::io::{ self, BufRead, Write };
+use rand::random;
+use game::{ Game, Rules, Status };
+use notation::{ parse_size, Position };
+
+pub struct Session {
+    rules: Rules,
+    /// Seed of new games when none is given, a random one if `None`.
+    seed: Option<u64>,
+    game: Game,
+}
+
+impl Session {
+    pub fn new(rules: Rules, seed: Option<u64>) -> Session {
+        let game = Game::new(rules.clone(), seed.unwrap_or_else(random));
+        Session {
+            rules,
+            seed,
+            game,
+        }
+    }
+
+    /// Carries out one command line and returns the reply, `None` on `quit`.
+    pub fn execute(&mut self, line: &str) -> Option<String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let (command, args) = match words.split_first() {
+            Some((command, args)) => (*command, args),
+            None => return Some("error empty command".to_string()),
+        };
+
+        let reply = match command {
+            "new" => self.new_game(args),
+            "position" => self.position(args),
+            "board" => self.game.position().map(|position| format!("board {}", position)),
+            "score" => Ok(format!("score {}", self.game.score())),
+            "moves" => {
+                let moves: Vec<&str> = self.game.legal_moves().iter().map(|direction| direction.name()).collect();
+                Ok(format!("moves {}", moves.join(" ")).trim_end().to_string())
+            },
+            "move" => self.make_move(args),
+            "continue" => {
+                self.game.keep_playing();
+                Ok("ok".to_string())
+            },
+            "status" => Ok(format!("status {}", status_name(self.game.status()))),
+            "over" => Ok(format!("over {}", if self.game.status() == Status::GameOver { "yes" } else { "no" })),
+            "quit" => return None,
+            command => Err(format!("unknown command {}", command)),
+        };
+
+        Some(reply.unwrap_or_else(|e| format!("error {}", e)))
+    }
+
+    fn new_game(&mut self, args: &[&str]) -> Result<String, String> {
+        let mut rules = self.rules.clone();
+        let mut seed = self.seed;
+
+        for pair in args.chunks(2) {
+            match (pair[0], pair.get(1)) {
+                ("seed", Some(value)) => {
+                    seed = Some(value.parse().map_err(|_| format!("invalid seed {}", value))?);
+                },
+                ("size", Some(value)) => {
+                    let (width, height) = parse_size(value).ok_or_else(|| format!("invalid size {}", value))?;
+                    rules.width = width;
+                    rules.height = height;
+                },
+                (name, _) => return Err(format!("invalid argument {}", name)),
+            }
+        }
+
+        rules.check()?;
+        let seed = seed.unwrap_or_else(random);
+        self.game = Game::new(rules, seed);
+        Ok(format!("ok {}", seed))
+    }
+
+    fn position(&mut self, args: &[&str]) -> Result<String, String> {
+        let position: Position = args.join(" ").parse()?;
+        let seed = self.seed.unwrap_or_else(random);
+        self.game = Game::from_position(self.rules.clone(), seed, &position)?;
+        Ok("ok".to_string())
+    }
+
+    fn make_move(&mut self, args: &[&str]) -> Result<String, String> {
+        let direction = match args {
+            [direction] => direction.parse()?,
+            _ => return Err("move expects a direction".to_string()),
+        };
+
+        if !self.game.can_move_towards(direction) {
+            return Err(format!("illegal move {}", args[0]));
+        }
+
+        let result = self.game.move_tiles(direction);
+        Ok(format!("moved {}", result.score_gained))
+    }
+}
+
+fn status_name(status: Status) -> &'static str {
+    match status {
+        Status::Playing => "playing",
+        Status::Won => "won",
+        Status::KeepPlaying => "keep-playing",
+        Status::GameOver => "game-over",
+    }
+}
+
+/// Answers the commands read from `input` until `quit` or the end of the
+/// input.
+pub fn run<R: BufRead, W: Write>(rules: Rules, seed: Option<u64>, input: R, output: &mut W) -> io::Result<()> {
+    let mut session = Session::new(rules, seed);
+
+    for line in input.lines() {
+        match session.execute(&line?) {
+            Some(reply) => {
+                writeln!(output, "{}", reply)?;
+                output.flush()?;
+            },
+            None => break,
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replies(input: &str) -> Vec<String> {
+        let mut output = Vec::new();
+        run(Rules::default(), Some(0), input.as_bytes(), &mut output).unwrap();
+        String::from_utf8(output).unwrap().lines().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn answers_every_command() {
+        let input = "position 4x4 1100/0000/0000/0000 0 m\nboard\nmoves\nmove left\nscore\n\
+                     status\nover\ncontinue\nquit\nscore\n";
+        assert_eq!(replies(input), [
+            "ok",
+            "board 4x4 1100/0000/0000/0000 0 m",
+            "moves down left right",
+            "moved 4",
+            "score 4",
+            "status playing",
+            "over no",
+            "ok",
+        ]);
+    }
+
+    #[test]
+    fn starts_new_games() {
+        let replies = replies("new seed 42 size 3x5\nboard\nnew size 100000x100000\nnew seed x\nnew size\n");
+        assert_eq!(replies[0], "ok 42");
+        assert!(replies[1].starts_with("board 3x5 "));
+        assert_eq!(replies[2], "error invalid board size 100000x100000, boards go from 1x1 to 64x64");
+        assert_eq!(replies[3], "error invalid seed x");
+        assert_eq!(replies[4], "error invalid argument size");
+    }
+
+    #[test]
+    fn rejects_bad_commands() {
+        let mut session = Session::new(Rules::default(), Some(0));
+        session.execute("position 2x2 12/21 0 m");
+
+        assert_eq!(session.execute(""), Some("error empty command".to_string()));
+        assert_eq!(session.execute("jump"), Some("error unknown command jump".to_string()));
+        assert_eq!(session.execute("move"), Some("error move expects a direction".to_string()));
+        assert_eq!(session.execute("move sideways"), Some("error invalid direction sideways".to_string()));
+        assert_eq!(session.execute("move up"), Some("error illegal move up".to_string()));
+        assert_eq!(session.execute("over"), Some("over yes".to_string()));
+    }
+}
@@ -0,0 +1,54 @@
+//! Replays: a game written down as its rules, seed and moves.
+//!
+//! Tiles spawn the same way for the same rules and seed, so the moves are
+//! enough to play the whole game again.
+
+use game::{ Direction, Game, MoveResult, Rules, Status };
+
+#[derive(Clone, Debug, PartialEq, RustcEncodable, RustcDecodable)]
+pub struct Replay {
+    pub rules: Rules,
+    pub seed: u64,
+    /// Notation of the position the game started from, `None` if it
+    /// started as a new game.
+    pub start: Option<String>,
+    /// Moves which changed the board, in the order they were made.
+    pub directions: Vec<Direction>,
+}
+
+impl Replay {
+    /// Game at the start of the replay, before any move.
+    pub fn start(&self) -> Result<Game, String> {
+        match self.start {
+            Some(ref start) => Game::from_position(self.rules.clone(), self.seed, &start.parse()?),
+            None => {
+                self.rules.check()?;
+                Ok(Game::new(self.rules.clone(), self.seed))
+            },
+        }
+    }
+
+    /// Makes the `i`th move of the replay on `game`, keeping playing if
+    /// the game has just been won as the player must have done.
+    pub fn play_move(&self, game: &mut Game, i: usize) -> MoveResult {
+        if game.status() == Status::Won {
+            game.keep_playing();
+        }
+
+        game.move_tiles(self.directions[i])
+    }
+
+    /// Plays the first `count` moves from the start. Fails if any of them
+    /// doesn't change the board, as the replay doesn't match its game then.
+    pub fn play(&self, count: usize) -> Result<Game, String> {
+        let mut game = self.start()?;
+
+        for i in 0..count.min(self.directions.len()) {
+            if !self.play_move(&mut game, i).changed {
+                return Err(format!("move {} ({:?}) doesn't change the board", i + 1, self.directions[i]));
+            }
+        }
+
+        Ok(game)
+    }
+}
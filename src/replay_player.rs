@@ -0,0 +1,94 @@
+//! Plays a replay back on the board, through the usual animations.
+
+use rust_2048::replay::Replay;
+use board::Board;
+
+/// Moves per second when a replay starts.
+static DEFAULT_SPEED: f64 = 4.0;
+static MIN_SPEED: f64 = 0.5;
+static MAX_SPEED: f64 = 64.0;
+
+pub struct ReplayPlayer {
+    replay: Replay,
+    paused: bool,
+    /// Moves per second.
+    speed: f64,
+    /// Time left before the next move.
+    wait: f64,
+}
+
+impl ReplayPlayer {
+    /// Plays `replay` on a board showing the start of its game.
+    pub fn new(replay: Replay) -> ReplayPlayer {
+        ReplayPlayer {
+            replay,
+            paused: false,
+            speed: DEFAULT_SPEED,
+            wait: 1.0 / DEFAULT_SPEED,
+        }
+    }
+
+    pub fn update(&mut self, board: &mut Board, dt: f64) {
+        if self.paused || board.is_locking() {
+            return;
+        }
+
+        self.wait -= dt;
+        if self.wait <= 0.0 {
+            self.wait = 1.0 / self.speed;
+            self.play_next(board);
+        }
+    }
+
+    /// Pauses and makes the next move.
+    pub fn step_forward(&mut self, board: &mut Board) {
+        self.paused = true;
+        self.play_next(board);
+    }
+
+    /// Pauses and takes back the last move.
+    pub fn step_back(&mut self, board: &mut Board) {
+        self.paused = true;
+        board.undo();
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Moves per second.
+    pub fn speed(&self) -> f64 {
+        self.speed
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    pub fn faster(&mut self) {
+        self.speed = (self.speed * 2.0).min(MAX_SPEED);
+    }
+
+    pub fn slower(&mut self) {
+        self.speed = (self.speed / 2.0).max(MIN_SPEED);
+    }
+
+    /// Where the replay is at, for the window title.
+    pub fn describe(&self, board: &Board) -> String {
+        let state = if self.paused {
+            "paused".to_string()
+        } else {
+            format!("{} moves/s", self.speed)
+        };
+
+        format!("replay of seed {}, move {}/{}, {}",
+                self.replay.seed, board.moves(), self.replay.directions.len(), state)
+    }
+
+    fn play_next(&self, board: &mut Board) {
+        let i = board.moves() as usize;
+        if i < self.replay.directions.len() {
+            board.play_replay_move(&self.replay, i);
+        }
+    }
+}
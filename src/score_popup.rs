@@ -0,0 +1,82 @@
+//! Points scored by a move floating up from the score box as "+N", and a
+//! short pulse of the score itself.
+
+use piston_window::*;
+use opengl_graphics::GlGraphics;
+use rust_2048::animation::{ Animation, Easing };
+use rust_2048::theme::Theme;
+use number_renderer::NumberRenderer;
+
+static POPUP_TIME: f64 = 0.8;
+/// How far a popup rises before it is gone.
+static POPUP_RISE: f64 = 32.0;
+static PULSE_TIME: f64 = 0.2;
+/// Scale of the score at the height of a pulse.
+static PULSE_SCALE: f64 = 1.2;
+
+pub struct ScorePopups {
+    /// Points scored by a move, and when they show.
+    popups: Vec<(i32, Animation)>,
+    pulse: Option<Animation>,
+    /// Time of the last update, on the clock of the board.
+    now: f64,
+}
+
+impl ScorePopups {
+    pub fn new() -> ScorePopups {
+        ScorePopups {
+            popups: Vec::new(),
+            pulse: None,
+            now: 0.0,
+        }
+    }
+
+    /// Shows the points scored by a move, all its merges together.
+    pub fn add(&mut self, score: i32, now: f64) {
+        self.popups.push((score, Animation::new(now, POPUP_TIME, Easing::EaseOutCubic)));
+        self.pulse = Some(Animation::new(now, PULSE_TIME, Easing::Linear));
+    }
+
+    pub fn clear(&mut self) {
+        self.popups.clear();
+        self.pulse = None;
+    }
+
+    /// Drops what is over at `now`.
+    pub fn update(&mut self, now: f64) {
+        self.now = now;
+        self.popups.retain(|&(_, animation)| !animation.is_finished(now));
+        if let Some(pulse) = self.pulse {
+            if pulse.is_finished(now) {
+                self.pulse = None;
+            }
+        }
+    }
+
+    /// Scale of the score, which grows and shrinks back during a pulse.
+    pub fn score_scale(&self) -> f64 {
+        match self.pulse {
+            Some(pulse) => {
+                let progress = pulse.progress(self.now);
+                1.0 + (PULSE_SCALE - 1.0) * (1.0 - (2.0 * progress - 1.0).abs())
+            },
+            None => 1.0,
+        }
+    }
+
+    /// Draws the popups rising from the middle of the score box filling
+    /// `rect`.
+    pub fn render(&self, theme: &Theme, number_renderer: &NumberRenderer, rect: [f64; 4],
+                  c: &Context, gl: &mut GlGraphics) {
+        let color = theme.text_dark_color;
+
+        for &(score, animation) in self.popups.iter() {
+            let progress = animation.progress(self.now);
+            let alpha = 1.0 - progress as f32;
+            let y = rect[1] + rect[3] / 2.0 - progress * POPUP_RISE;
+
+            number_renderer.render_with_sign(score, rect[0] + rect[2] / 2.0, y, rect[2],
+                                             [color[0], color[1], color[2], alpha], c, gl);
+        }
+    }
+}
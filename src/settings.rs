@@ -4,13 +4,21 @@ use std::io::{BufWriter, BufReader, Write};
 use std::fs::{File};
 use std::path::Path;
 use rustc_serialize::{ json, Encodable, Decodable };
+use ai::{ Ai, Heuristic };
+use animation::Easing;
+use game::Rules;
+use theme;
 
 static SETTING_FILENAME: &'static str = "settings.json";
+/// Moves per second played by autoplay.
+static DEFAULT_AUTOPLAY_RATE: f64 = 8.0;
+/// Moves kept while tiles are still moving.
+static DEFAULT_INPUT_QUEUE_LENGTH: usize = 2;
+static DEFAULT_EASING: Easing = Easing::Linear;
 
 pub struct Settings {
     pub asset_folder: String,
     pub window_size: [u32; 2],
-    pub window_background_color: [f32; 3],
     pub comment1_offset_y: f64,
     pub comment2_offset_y: f64,
     pub board_padding: f64,
@@ -18,20 +26,35 @@ pub struct Settings {
     pub board_offset_y: f64,
     pub tile_width: i32,
     pub tile_height: i32,
+    pub target_tile: i32,
+    /// Seed of every new game, a random one is picked for each game if `None`.
+    pub seed: Option<u64>,
+    /// (score, weight) of the tiles which can spawn.
+    pub spawn_table: Vec<(i32, u32)>,
+    pub spawn_per_move: u32,
+    pub start_tiles: u32,
+    /// Computer player of autoplay.
+    pub ai: Ai,
+    /// Moves per second played by autoplay.
+    pub autoplay_rate: f64,
+    /// Moves kept while tiles are still moving, made once they stop. Moves
+    /// beyond it are dropped.
+    pub input_queue_length: usize,
     pub tile_size: f64,
     pub tile_padding: f64,
-    pub tile_background_color: [f32; 3],
-    pub tiles_colors: Vec<[f32; 3]>,
-    pub tile_unknow_color: [f32; 3],
+    /// Name of the theme to start with, a file in the `themes` folder of the
+    /// assets.
+    pub theme: String,
     pub tile_move_time: f64,
     pub tile_new_time: f64,
     pub tile_combine_time: f64,
+    pub tile_move_easing: Easing,
+    pub tile_new_easing: Easing,
+    pub tile_combine_easing: Easing,
+    /// Whether moves show up right away, without animations.
+    pub reduced_motion: bool,
     pub best_rect: [f64; 4],
     pub score_rect: [f64; 4],
-    pub label_color: [f32; 3],
-    pub button_color: [f32; 3],
-    pub text_dark_color: [f32; 3],
-    pub text_light_color: [f32; 3],
 }
 
 impl Settings {
@@ -39,22 +62,60 @@ impl Settings {
         Settings::from_settings_in_json(&SettingsInJson::load())
     }
 
+    /// Takes the seed of `--seed <n>` among the command line `args` over the
+    /// one of the settings file. A seed which isn't a number is ignored.
+    pub fn read_seed_arg<I: IntoIterator<Item = String>>(&mut self, args: I) {
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            if arg == "--seed" {
+                match args.next().map(|seed| seed.parse()) {
+                    Some(Ok(seed)) => self.seed = Some(seed),
+                    _ => println!("WARNING: --seed expects a number, ignoring it."),
+                }
+            }
+        }
+    }
+
+    /// Rules of the games to play.
+    pub fn rules(&self) -> Rules {
+        Rules {
+            width: self.tile_width,
+            height: self.tile_height,
+            target: self.target_tile,
+            spawn_table: self.spawn_table.clone(),
+            spawn_per_move: self.spawn_per_move,
+            start_tiles: self.start_tiles,
+        }
+    }
+
+    /// Seconds the animations of a move last, from the first tile sliding
+    /// to the last one settling.
+    pub fn move_animation_time(&self) -> f64 {
+        (self.tile_move_time + self.tile_combine_time).max(self.tile_new_time)
+    }
+
     fn from_settings_in_json<'a>(s: &'a SettingsInJson) -> Settings {
+        let default_rules = Rules::default();
+        let default_ai = Ai::default();
+
+        let spawn_table = match s.spawn_table {
+            Some(ref table) if table.iter().any(|&(score, _)| score <= 0) => {
+                println!("WARNING: spawn_table has a tile with a score of 0 or less, using the default one.");
+                default_rules.spawn_table.clone()
+            },
+            Some(ref table) if table.iter().any(|&(_, weight)| weight > 0) => table.clone(),
+            Some(_) => {
+                println!("WARNING: spawn_table has no tile with a weight, using the default one.");
+                default_rules.spawn_table.clone()
+            },
+            None => default_rules.spawn_table.clone(),
+        };
+
         let board_size = [
             s.tile_size * s.tile_width as f64 + s.tile_padding * (s.tile_width + 1) as f64,
             s.tile_size * s.tile_height as f64 + s.tile_padding * (s.tile_height + 1) as f64,
         ];
 
-        let mut tiles_colors = Vec::<[f32; 3]>::new();
-
-        for color in s.tiles_colors.iter() {
-            tiles_colors.push([
-                color[0] / 255.0,
-                color[1] / 255.0,
-                color[2] / 255.0,
-            ]);
-        }
-
         Settings {
             asset_folder: s.asset_folder.clone(),
             comment1_offset_y: s.comment1_offset_y,
@@ -63,32 +124,32 @@ impl Settings {
                 (s.board_padding * 2.0 + board_size[0]) as u32,
                 (s.board_padding * 2.0 + board_size[1] + s.board_offset_y) as u32,
             ],
-            window_background_color: [
-                s.window_background_color[0] / 255.0,
-                s.window_background_color[1] / 255.0,
-                s.window_background_color[2] / 255.0,
-            ],
             board_padding: s.board_padding,
             board_size: board_size,
             board_offset_y: s.board_offset_y,
             tile_width: s.tile_width,
             tile_height: s.tile_height,
+            target_tile: s.target_tile.unwrap_or(default_rules.target),
+            seed: s.seed,
+            spawn_table,
+            spawn_per_move: s.spawn_per_move.unwrap_or(default_rules.spawn_per_move),
+            start_tiles: s.start_tiles.unwrap_or(default_rules.start_tiles),
+            ai: Ai {
+                depth: s.ai_depth.unwrap_or(default_ai.depth),
+                heuristic: s.ai_heuristic.clone().unwrap_or(default_ai.heuristic),
+            },
+            autoplay_rate: s.autoplay_rate.unwrap_or(DEFAULT_AUTOPLAY_RATE),
+            input_queue_length: s.input_queue_length.unwrap_or(DEFAULT_INPUT_QUEUE_LENGTH),
             tile_size: s.tile_size,
             tile_padding: s.tile_padding,
-            tile_background_color: [
-                s.tile_background_color[0] / 255.0,
-                s.tile_background_color[1] / 255.0,
-                s.tile_background_color[2] / 255.0,
-            ],
-            tiles_colors: tiles_colors,
-            tile_unknow_color: [
-                s.tile_unknow_color[0] / 255.0,
-                s.tile_unknow_color[1] / 255.0,
-                s.tile_unknow_color[2] / 255.0,
-            ],
+            theme: s.theme.clone().unwrap_or_else(|| theme::CLASSIC.to_string()),
             tile_move_time: s.tile_move_time,
             tile_new_time: s.tile_new_time,
             tile_combine_time: s.tile_combine_time,
+            tile_move_easing: easing("tile_move_easing", &s.tile_move_easing),
+            tile_new_easing: easing("tile_new_easing", &s.tile_new_easing),
+            tile_combine_easing: easing("tile_combine_easing", &s.tile_combine_easing),
+            reduced_motion: s.reduced_motion.unwrap_or(false),
             best_rect: [
                 s.best_rect[0],
                 s.best_rect[1],
@@ -101,37 +162,26 @@ impl Settings {
                 s.score_rect[2],
                 s.score_rect[3],
             ],
-            label_color: [
-                s.label_color[0] / 255.0,
-                s.label_color[1] / 255.0,
-                s.label_color[2] / 255.0,
-            ],
-            button_color: [
-                s.button_color[0] / 255.0,
-                s.button_color[1] / 255.0,
-                s.button_color[2] / 255.0,
-            ],
-            text_dark_color: [
-                s.text_dark_color[0] / 255.0,
-                s.text_dark_color[1] / 255.0,
-                s.text_dark_color[2] / 255.0,
-            ],
-            text_light_color: [
-                s.text_light_color[0] / 255.0,
-                s.text_light_color[1] / 255.0,
-                s.text_light_color[2] / 255.0,
-            ],
         }
     }
 }
 
+/// Easing named `name` in `settings.json`, the default one if it is missing
+/// or unknown.
+fn easing(key: &str, name: &Option<String>) -> Easing {
+    match *name {
+        Some(ref name) => name.parse().unwrap_or_else(|e| {
+            println!("WARNING: {} is invalid ({}), using {}.", key, e, DEFAULT_EASING.name());
+            DEFAULT_EASING
+        }),
+        None => DEFAULT_EASING,
+    }
+}
+
 #[derive(RustcEncodable, RustcDecodable)]
 struct SettingsInJson {
     asset_folder: String,
 
-    // r g b (0 - 255)
-    window_background_color: Vec<f32>,
-
     comment1_offset_y: f64,
     comment2_offset_y: f64,
 
@@ -140,71 +190,74 @@ struct SettingsInJson {
 
     tile_width: i32,
     tile_height: i32,
+    // missing in settings files written by older versions
+    target_tile: Option<i32>,
+    seed: Option<u64>,
+    // [score, weight] of each tile which can spawn
+    spawn_table: Option<Vec<(i32, u32)>>,
+    spawn_per_move: Option<u32>,
+    start_tiles: Option<u32>,
+    // moves searched ahead by the computer player
+    ai_depth: Option<u32>,
+    ai_heuristic: Option<Heuristic>,
+    // moves per second
+    autoplay_rate: Option<f64>,
+    // moves kept while tiles are moving, 0 drops them
+    input_queue_length: Option<usize>,
     tile_size: f64,
     tile_padding: f64,
-    tile_background_color: Vec<f32>,
-    tiles_colors: Vec<Vec<f32>>,
-    tile_unknow_color: Vec<f32>,
+    // colors are in themes, settings files written by older versions still
+    // have them but they are ignored
+    theme: Option<String>,
 
     tile_move_time: f64,
     tile_new_time: f64,
     tile_combine_time: f64,
+    // linear, ease_out_cubic or back
+    tile_move_easing: Option<String>,
+    tile_new_easing: Option<String>,
+    tile_combine_easing: Option<String>,
+    // no animations at all
+    reduced_motion: Option<bool>,
 
     best_rect: Vec<f64>,
     score_rect: Vec<f64>,
-
-    label_color: Vec<f32>,
-    button_color: Vec<f32>,
-    text_dark_color: Vec<f32>,
-    text_light_color: Vec<f32>,
 }
 
 impl SettingsInJson {
     pub fn default_settings() -> SettingsInJson {
-        let mut tiles_colors = Vec::<Vec<f32>>::new();
-        // empty color
-        tiles_colors.push(vec![204.0, 192.0, 179.0]);
-        // 2 color
-        tiles_colors.push(vec![238.0, 228.0, 218.0]);
-        // 4 color
-        tiles_colors.push(vec![237.0, 224.0, 200.0]);
-        // 8 color
-        tiles_colors.push(vec![242.0, 177.0, 121.0]);
-        // 16 color
-        tiles_colors.push(vec![245.0, 149.0, 99.0]);
-        // 32 color
-        tiles_colors.push(vec![246.0, 124.0, 95.0]);
-        // 64 color
-        tiles_colors.push(vec![246.0, 94.0, 59.0]);
-        // 128 color
-        tiles_colors.push(vec![237.0, 207.0, 114.0]);
-        // 256 color
-        tiles_colors.push(vec![237.0, 204.0, 97.0]);
-        // 512 color
-        tiles_colors.push(vec![237.0, 200.0, 80.0]);
+        let default_rules = Rules::default();
+        let default_ai = Ai::default();
+
         SettingsInJson {
             asset_folder: "bin/assets".to_string(),
-            window_background_color: vec![255.0, 248.0, 239.0],
             comment1_offset_y: 72.0,
             comment2_offset_y: 100.0,
             board_padding: 12.0,
             board_offset_y: 128.0,
             tile_width: 4,
             tile_height: 4,
+            target_tile: Some(default_rules.target),
+            seed: None,
+            spawn_table: Some(default_rules.spawn_table),
+            spawn_per_move: Some(default_rules.spawn_per_move),
+            start_tiles: Some(default_rules.start_tiles),
+            ai_depth: Some(default_ai.depth),
+            ai_heuristic: Some(default_ai.heuristic),
+            autoplay_rate: Some(DEFAULT_AUTOPLAY_RATE),
+            input_queue_length: Some(DEFAULT_INPUT_QUEUE_LENGTH),
             tile_size: 72.0,
             tile_padding: 16.0,
-            tile_background_color: vec![187.0, 173.0, 160.0],
-            tiles_colors: tiles_colors,
-            tile_unknow_color: vec![200.0, 0.0, 0.0],
+            theme: Some(theme::CLASSIC.to_string()),
             tile_move_time: 0.1,
             tile_new_time: 0.1,
             tile_combine_time: 0.1,
+            tile_move_easing: Some(DEFAULT_EASING.name().to_string()),
+            tile_new_easing: Some(DEFAULT_EASING.name().to_string()),
+            tile_combine_easing: Some(DEFAULT_EASING.name().to_string()),
+            reduced_motion: Some(false),
             best_rect: vec![284.0, 12.0, 96.0, 48.0,],
             score_rect: vec![176.0, 12.0, 96.0, 48.0],
-            label_color: vec![187.0, 173.0, 160.0],
-            button_color: vec![142.0, 122.0, 102.0],
-            text_dark_color: vec![119.0, 110.0, 101.0],
-            text_light_color: vec![249.0, 246.0, 242.0],
         }
     }
 
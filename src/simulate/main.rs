@@ -0,0 +1,102 @@
+//! Plays batches of headless games and reports how they went:
+//!
+//! ```text
+//! rust-2048-simulate [--games <n>] [--seed <first seed>]
+//!                    [--strategy ai|random|greedy|corner] [--depth <n>]
+//!                    [--heuristic <empty>,<monotonicity>,<smoothness>,<corner>]
+//!                    [--json]
+//! ```
+//!
+//! The rules and the defaults of the computer player come from
+//! `settings.json`.
+
+extern crate rustc_serialize;
+extern crate rust_2048;
+
+use std::process;
+use rustc_serialize::json;
+use rust_2048::ai::Heuristic;
+use rust_2048::settings::Settings;
+use rust_2048::simulation::{ self, Strategy };
+
+struct Options {
+    games: usize,
+    first_seed: u64,
+    strategy: Strategy,
+    json: bool,
+}
+
+fn parse_heuristic(s: &str) -> Result<Heuristic, String> {
+    let weights: Vec<f64> = s.split(',')
+        .map(|weight| weight.trim().parse().map_err(|_| format!("invalid weight {}", weight)))
+        .collect::<Result<_, _>>()?;
+
+    match weights[..] {
+        [empty_cells, monotonicity, smoothness, max_in_corner] => Ok(Heuristic {
+            empty_cells,
+            monotonicity,
+            smoothness,
+            max_in_corner,
+        }),
+        _ => Err(format!("expected 4 weights but found {}", weights.len())),
+    }
+}
+
+fn parse_args(settings: &Settings) -> Result<Options, String> {
+    let mut options = Options {
+        games: 100,
+        first_seed: 0,
+        strategy: Strategy::Ai(settings.ai.clone()),
+        json: false,
+    };
+    let mut strategy = "ai".to_string();
+    let mut ai = settings.ai.clone();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--json" {
+            options.json = true;
+            continue;
+        }
+
+        let value = args.next().ok_or_else(|| format!("{} expects a value", arg))?;
+        match &arg[..] {
+            "--games" => options.games = value.parse().map_err(|_| format!("invalid number of games {}", value))?,
+            "--seed" => options.first_seed = value.parse().map_err(|_| format!("invalid seed {}", value))?,
+            "--strategy" => strategy = value,
+            "--depth" => ai.depth = value.parse().map_err(|_| format!("invalid depth {}", value))?,
+            "--heuristic" => ai.heuristic = parse_heuristic(&value)?,
+            _ => return Err(format!("unknown option {}", arg)),
+        }
+    }
+
+    options.strategy = match &strategy[..] {
+        "ai" => Strategy::Ai(ai),
+        "random" => Strategy::Random,
+        "greedy" => Strategy::Greedy,
+        "corner" => Strategy::Corner,
+        _ => return Err(format!("unknown strategy {}", strategy)),
+    };
+
+    Ok(options)
+}
+
+fn main() {
+    let settings = Settings::load();
+
+    let options = match parse_args(&settings) {
+        Ok(options) => options,
+        Err(e) => {
+            println!("ERROR: {}", e);
+            process::exit(1);
+        },
+    };
+
+    let summary = simulation::simulate(&settings.rules(), &options.strategy, options.first_seed, options.games);
+
+    if options.json {
+        println!("{}", json::as_pretty_json(&summary));
+    } else {
+        print!("{}", summary.to_text());
+    }
+}
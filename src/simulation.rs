@@ -0,0 +1,235 @@
+//! Plays batches of games without a window, to compare strategies and to
+//! notice when a change to the rules alters how games turn out.
+
+use std::time::Instant;
+use rand::Rng;
+use ai::Ai;
+use game::{ Direction, Game, Rules, Status };
+use rng::GameRng;
+
+/// How the moves of simulated games are picked.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Strategy {
+    /// Expectimax search.
+    Ai(Ai),
+    /// Any legal move.
+    Random,
+    /// The move which scores the most right away.
+    Greedy,
+    /// The first legal move of down, left, right and up, which keeps the
+    /// high tiles in the bottom left corner.
+    Corner,
+}
+
+impl Strategy {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Strategy::Ai(_) => "ai",
+            Strategy::Random => "random",
+            Strategy::Greedy => "greedy",
+            Strategy::Corner => "corner",
+        }
+    }
+
+    /// Move to make in `game`, `None` if no move is left.
+    fn choose(&self, game: &Game, rng: &mut GameRng) -> Option<Direction> {
+        let legal = game.legal_moves();
+        if legal.is_empty() {
+            return None;
+        }
+
+        match *self {
+            Strategy::Ai(ref ai) => ai.best_move(game),
+            Strategy::Random => Some(legal[rng.gen_range(0, legal.len())]),
+            Strategy::Greedy => legal.iter().cloned().max_by_key(|&direction| {
+                let mut copy = game.copy_without_history();
+                copy.move_tiles(direction).score_gained
+            }),
+            Strategy::Corner => [Direction::Down, Direction::Left, Direction::Right, Direction::Up]
+                .iter().cloned().find(|direction| legal.contains(direction)),
+        }
+    }
+}
+
+/// How a single game ended.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GameResult {
+    pub seed: u64,
+    pub score: i32,
+    pub moves: u32,
+    pub max_tile: i32,
+}
+
+/// Plays a game with `strategy` until no move is left, going on after the
+/// target tile is reached. `rules` must pass `Rules::check`.
+pub fn play(rules: &Rules, seed: u64, strategy: &Strategy) -> GameResult {
+    let mut game = Game::new(rules.clone(), seed);
+    game.forget_history();
+    // the random strategy gets its own generator so that the tiles spawned
+    // stay the same as with any other strategy
+    let mut rng = GameRng::new(!seed);
+
+    loop {
+        if game.status() == Status::Won {
+            game.keep_playing();
+        }
+
+        let direction = match strategy.choose(&game, &mut rng) {
+            Some(direction) => direction,
+            None => break,
+        };
+
+        // a strategy stuck on a move which does nothing would never finish
+        if !game.move_tiles(direction).changed {
+            println!("WARNING: The {} strategy chose {} which doesn't move anything in game {}, stopping it.",
+                     strategy.name(), direction.name(), seed);
+            break;
+        }
+    }
+
+    GameResult {
+        seed,
+        score: game.score(),
+        moves: game.moves(),
+        max_tile: game.tiles().map(|tile| tile.score).max().unwrap_or(0),
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, RustcEncodable)]
+pub struct Percentile {
+    pub percent: u32,
+    pub score: i32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, RustcEncodable)]
+pub struct TileRate {
+    pub tile: i32,
+    /// Fraction of the games which reached the tile.
+    pub rate: f64,
+}
+
+#[derive(Clone, Debug, PartialEq, RustcEncodable)]
+pub struct Summary {
+    pub strategy: String,
+    pub games: usize,
+    pub first_seed: u64,
+    pub min_score: i32,
+    pub max_score: i32,
+    pub mean_score: f64,
+    /// Scores at the 10th, 25th, 50th, 75th and 90th percentiles.
+    pub score_percentiles: Vec<Percentile>,
+    /// Every power of two from 2 up to the highest tile reached.
+    pub tile_rates: Vec<TileRate>,
+    pub mean_moves: f64,
+    pub moves_per_second: f64,
+}
+
+/// Plays `games` games with `strategy`, with the seeds from `first_seed` on.
+pub fn simulate(rules: &Rules, strategy: &Strategy, first_seed: u64, games: usize) -> Summary {
+    let start = Instant::now();
+    let results: Vec<GameResult> = (0..games as u64)
+        .map(|i| play(rules, first_seed.wrapping_add(i), strategy))
+        .collect();
+    let elapsed = start.elapsed();
+    let seconds = elapsed.as_secs() as f64 + f64::from(elapsed.subsec_nanos()) / 1e9;
+
+    summarize(strategy, first_seed, &results, seconds)
+}
+
+fn summarize(strategy: &Strategy, first_seed: u64, results: &[GameResult], seconds: f64) -> Summary {
+    let count = results.len().max(1) as f64;
+    let mut scores: Vec<i32> = results.iter().map(|result| result.score).collect();
+    scores.sort();
+
+    let percentile = |percent: u32| -> i32 {
+        if scores.is_empty() {
+            return 0;
+        }
+        scores[(scores.len() - 1) * percent as usize / 100]
+    };
+
+    let highest = results.iter().map(|result| result.max_tile).max().unwrap_or(0);
+    let mut tile_rates = Vec::new();
+    let mut tile = 2;
+    while tile > 0 && tile <= highest {
+        let reached = results.iter().filter(|result| result.max_tile >= tile).count();
+        tile_rates.push(TileRate {
+            tile,
+            rate: reached as f64 / count,
+        });
+        tile = tile.checked_mul(2).unwrap_or(0);
+    }
+
+    let total_moves: u64 = results.iter().map(|result| u64::from(result.moves)).sum();
+
+    Summary {
+        strategy: strategy.name().to_string(),
+        games: results.len(),
+        first_seed,
+        min_score: scores.first().cloned().unwrap_or(0),
+        max_score: scores.last().cloned().unwrap_or(0),
+        mean_score: scores.iter().map(|&score| f64::from(score)).sum::<f64>() / count,
+        score_percentiles: [10, 25, 50, 75, 90].iter().map(|&percent| Percentile {
+            percent,
+            score: percentile(percent),
+        }).collect(),
+        tile_rates,
+        mean_moves: total_moves as f64 / count,
+        moves_per_second: if seconds > 0.0 { total_moves as f64 / seconds } else { 0.0 },
+    }
+}
+
+impl Summary {
+    /// Report for people to read.
+    pub fn to_text(&self) -> String {
+        let mut text = format!("{} games with the {} strategy, seeds {} to {}\n",
+                               self.games, self.strategy, self.first_seed,
+                               self.first_seed.wrapping_add(self.games as u64).wrapping_sub(1));
+
+        text.push_str(&format!("score: min {}, mean {:.0}, max {}\n", self.min_score, self.mean_score, self.max_score));
+        for percentile in self.score_percentiles.iter() {
+            text.push_str(&format!("  {:>3}%: {}\n", percentile.percent, percentile.score));
+        }
+
+        text.push_str("tiles reached:\n");
+        for tile_rate in self.tile_rates.iter() {
+            text.push_str(&format!("  {:>6}: {:.1}%\n", tile_rate.tile, tile_rate.rate * 100.0));
+        }
+
+        text.push_str(&format!("moves: {:.1} per game, {:.0} per second\n", self.mean_moves, self.moves_per_second));
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Summary of seeds 0 to 19, without the speed which changes from run
+    /// to run.
+    fn summary(strategy: &Strategy) -> Summary {
+        let mut summary = simulate(&Rules::default(), strategy, 0, 20);
+        summary.moves_per_second = 0.0;
+        summary
+    }
+
+    #[test]
+    fn same_seeds_same_summary() {
+        assert_eq!(summary(&Strategy::Random), summary(&Strategy::Random));
+
+        // these change only if the rules, the spawns or a strategy do
+        let corner = summary(&Strategy::Corner);
+        assert_eq!((corner.min_score, corner.max_score, corner.mean_score), (1216, 3468, 2396.0));
+        assert_eq!(corner.mean_moves, 216.55);
+        assert_eq!(corner.tile_rates.last(), Some(&TileRate { tile: 256, rate: 0.5 }));
+
+        let greedy = summary(&Strategy::Greedy);
+        assert_eq!((greedy.min_score, greedy.max_score, greedy.mean_score), (864, 8000, 3240.2));
+        assert_eq!(greedy.score_percentiles[2], Percentile { percent: 50, score: 2760 });
+        assert_eq!(greedy.mean_moves, 268.6);
+
+        let random = summary(&Strategy::Random);
+        assert_eq!((random.min_score, random.max_score), (424, 2928));
+        assert_eq!(random.mean_moves, 125.1);
+    }
+}
@@ -0,0 +1,55 @@
+//! JSON files kept next to the executable, like the settings.
+
+use std::env::current_exe;
+use std::io::{BufWriter, BufReader, Write};
+use std::fs::{self, File};
+use std::path::{ Path, PathBuf };
+use rustc_serialize::{ json, Encodable, Decodable };
+
+fn path(filename: &str) -> Result<PathBuf, String> {
+    current_exe()
+        .map(|path| path.with_file_name(filename))
+        .map_err(|_| "can't find exe path.".to_string())
+}
+
+/// Reads `filename`, `Ok(None)` if it doesn't exist.
+pub fn load<T: Decodable>(filename: &str) -> Result<Option<T>, String> {
+    let path = path(filename)?;
+
+    if !path.is_file() {
+        return Ok(None);
+    }
+
+    read(&path).map(Some)
+}
+
+/// Reads the file at `path`, wherever it is.
+pub fn read<T: Decodable>(path: &Path) -> Result<T, String> {
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    let mut reader = BufReader::new(file);
+    let json = json::Json::from_reader(&mut reader).map_err(|e| e.to_string())?;
+    let mut decoder = json::Decoder::new(json);
+    Decodable::decode(&mut decoder).map_err(|e| e.to_string())
+}
+
+/// Writes `filename`, which may be in a folder created if missing.
+pub fn save<T: Encodable>(filename: &str, value: &T) -> Result<(), String> {
+    let path = path(filename)?;
+    if let Some(folder) = path.parent() {
+        fs::create_dir_all(folder).map_err(|e| e.to_string())?;
+    }
+    let encoded = json::encode(value).map_err(|e| e.to_string())?;
+    let file = File::create(&path).map_err(|e| e.to_string())?;
+    let mut writer = BufWriter::new(file);
+    writer.write_all(encoded.as_bytes()).map_err(|e| e.to_string())
+}
+
+pub fn remove(filename: &str) -> Result<(), String> {
+    let path = path(filename)?;
+
+    if path.is_file() {
+        fs::remove_file(&path).map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,197 @@
+//! Colors of the game, loaded from named theme files so they can be
+//! switched while playing.
+//!
+//! A theme is a JSON file in the `themes` folder of the assets, named
+//! after the theme. Colors are r g b (0 - 255) and every one a theme
+//! leaves out is taken from the classic palette.
+
+use std::fs::{ self, File };
+use std::io::Read;
+use std::path::Path;
+use rustc_serialize::json;
+use game;
+
+pub static CLASSIC: &str = "classic";
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme {
+    pub name: String,
+    pub window_background_color: [f32; 3],
+    pub tile_background_color: [f32; 3],
+    /// Color of the tiles by exponent, the first one for empty cells.
+    pub tiles_colors: Vec<[f32; 3]>,
+    /// Color of the tiles past the end of `tiles_colors`.
+    pub tile_unknow_color: [f32; 3],
+    pub label_color: [f32; 3],
+    pub button_color: [f32; 3],
+    pub text_dark_color: [f32; 3],
+    pub text_light_color: [f32; 3],
+}
+
+impl Theme {
+    /// The original palette, which other themes fall back to.
+    pub fn classic() -> Theme {
+        let tiles_colors = [
+            // empty color
+            [204.0, 192.0, 179.0],
+            // 2 color
+            [238.0, 228.0, 218.0],
+            // 4 color
+            [237.0, 224.0, 200.0],
+            // 8 color
+            [242.0, 177.0, 121.0],
+            // 16 color
+            [245.0, 149.0, 99.0],
+            // 32 color
+            [246.0, 124.0, 95.0],
+            // 64 color
+            [246.0, 94.0, 59.0],
+            // 128 color
+            [237.0, 207.0, 114.0],
+            // 256 color
+            [237.0, 204.0, 97.0],
+            // 512 color
+            [237.0, 200.0, 80.0],
+        ];
+
+        Theme {
+            name: CLASSIC.to_string(),
+            window_background_color: rgb([255.0, 248.0, 239.0]),
+            tile_background_color: rgb([187.0, 173.0, 160.0]),
+            tiles_colors: tiles_colors.iter().map(|&color| rgb(color)).collect(),
+            tile_unknow_color: rgb([200.0, 0.0, 0.0]),
+            label_color: rgb([187.0, 173.0, 160.0]),
+            button_color: rgb([142.0, 122.0, 102.0]),
+            text_dark_color: rgb([119.0, 110.0, 101.0]),
+            text_light_color: rgb([249.0, 246.0, 242.0]),
+        }
+    }
+
+    /// Loads the theme in `path`, named after the file.
+    pub fn load(path: &Path) -> Result<Theme, String> {
+        let name = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+
+        let mut text = String::new();
+        File::open(path)
+            .and_then(|mut file| file.read_to_string(&mut text))
+            .map_err(|e| e.to_string())?;
+        let theme: ThemeInJson = json::decode(&text).map_err(|e| e.to_string())?;
+
+        Ok(Theme::from_theme_in_json(name, &theme))
+    }
+
+    /// Loads every theme in `folder`, in the order of their names. The
+    /// classic palette stands in when there is none.
+    pub fn load_all(folder: &Path) -> Vec<Theme> {
+        let mut paths: Vec<_> = match fs::read_dir(folder) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.extension() == Some("json".as_ref()))
+                .collect(),
+            Err(e) => {
+                println!("WARNING: Failed to read themes from {}: {}", folder.display(), e);
+                Vec::new()
+            },
+        };
+        paths.sort();
+
+        let mut themes = Vec::new();
+        for path in paths {
+            match Theme::load(&path) {
+                Ok(theme) => themes.push(theme),
+                Err(e) => println!("WARNING: Failed to load theme {}: {}", path.display(), e),
+            }
+        }
+
+        if themes.is_empty() {
+            themes.push(Theme::classic());
+        }
+        themes
+    }
+
+    /// Index of the theme named `name` in `themes`, the first one if there
+    /// is none.
+    pub fn find(themes: &[Theme], name: &str) -> usize {
+        match themes.iter().position(|theme| theme.name == name) {
+            Some(i) => i,
+            None => {
+                println!("WARNING: There is no theme {}, using {}.", name, themes[0].name);
+                0
+            },
+        }
+    }
+
+    /// Background color of a tile with `score`, 0 for an empty cell.
+    pub fn tile_color(&self, score: i32) -> [f32; 3] {
+        if score == 0 {
+            return self.tiles_colors[0];
+        }
+
+        let i = game::exponent(score) as usize;
+        if i > 0 && i < self.tiles_colors.len() {
+            self.tiles_colors[i]
+        } else {
+            self.tile_unknow_color
+        }
+    }
+
+    /// Color of the number written on a tile with `score`.
+    pub fn text_color(&self, score: i32) -> [f32; 3] {
+        if score >= 8 {
+            self.text_light_color
+        } else {
+            self.text_dark_color
+        }
+    }
+
+    fn from_theme_in_json(name: String, t: &ThemeInJson) -> Theme {
+        let classic = Theme::classic();
+        let color = |key: &str, value: Option<&Vec<f32>>, fallback: [f32; 3]| -> [f32; 3] {
+            match value {
+                Some(value) if value.len() == 3 => rgb([value[0], value[1], value[2]]),
+                Some(_) => {
+                    println!("WARNING: {} of theme {} is not an r g b color, using the classic one.", key, name);
+                    fallback
+                },
+                None => fallback,
+            }
+        };
+
+        // tiles past the end of the list keep their classic color
+        let colors = t.tiles_colors.clone().unwrap_or_default();
+        let count = colors.len().max(classic.tiles_colors.len());
+        let tiles_colors = (0..count).map(|i| {
+            let fallback = classic.tiles_colors.get(i).cloned().unwrap_or(classic.tile_unknow_color);
+            color("tiles_colors", colors.get(i), fallback)
+        }).collect();
+
+        Theme {
+            window_background_color: color("window_background_color", t.window_background_color.as_ref(), classic.window_background_color),
+            tile_background_color: color("tile_background_color", t.tile_background_color.as_ref(), classic.tile_background_color),
+            tiles_colors,
+            tile_unknow_color: color("tile_unknow_color", t.tile_unknow_color.as_ref(), classic.tile_unknow_color),
+            label_color: color("label_color", t.label_color.as_ref(), classic.label_color),
+            button_color: color("button_color", t.button_color.as_ref(), classic.button_color),
+            text_dark_color: color("text_dark_color", t.text_dark_color.as_ref(), classic.text_dark_color),
+            text_light_color: color("text_light_color", t.text_light_color.as_ref(), classic.text_light_color),
+            name,
+        }
+    }
+}
+
+fn rgb(color: [f32; 3]) -> [f32; 3] {
+    [color[0] / 255.0, color[1] / 255.0, color[2] / 255.0]
+}
+
+// every color is optional, see `from_theme_in_json`
+#[derive(RustcDecodable)]
+struct ThemeInJson {
+    window_background_color: Option<Vec<f32>>,
+    tile_background_color: Option<Vec<f32>>,
+    tiles_colors: Option<Vec<Vec<f32>>>,
+    tile_unknow_color: Option<Vec<f32>>,
+    label_color: Option<Vec<f32>>,
+    button_color: Option<Vec<f32>>,
+    text_dark_color: Option<Vec<f32>>,
+    text_light_color: Option<Vec<f32>>,
+}
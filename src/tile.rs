@@ -1,17 +1,22 @@
 use piston_window::*;
 use opengl_graphics::GlGraphics;
 use number_renderer::NumberRenderer;
-use settings::Settings;
+use rust_2048::animation::Animation;
+use rust_2048::settings::Settings;
+use rust_2048::theme::Theme;
+
+/// Scale a combined tile pops out at before shrinking back.
+static COMBINE_SCALE: f64 = 1.2;
 
 #[derive(Clone, PartialEq)]
 pub enum TileState {
     TileStatic,
-    /// (t, x, y, origin_x, origin_y)
-    TileMoving(f64, f64, f64, i32, i32),
-    /// (t, size)
-    TileNew(f64, f64),
-    /// (t, size)
-    TileCombine(f64, f64),
+    /// (animation, origin_x, origin_y)
+    TileMoving(Animation, i32, i32),
+    /// Growing from nothing.
+    TileNew(Animation),
+    /// Shrinking from `COMBINE_SCALE` back to its size.
+    TileCombine(Animation),
 }
 
 #[derive(Clone)]
@@ -25,23 +30,36 @@ pub struct Tile<'a> {
 }
 
 impl<'a> Tile<'a> {
-    pub fn new(settings: &'a Settings, score: i32, tile_x: i32, tile_y: i32) -> Tile<'a> {
+    /// A tile which appears at `now`.
+    pub fn new(settings: &'a Settings, score: i32, tile_x: i32, tile_y: i32, now: f64) -> Tile<'a> {
         Tile {
             score: score,
             tile_x: tile_x,
             tile_y: tile_y,
-            status: TileState::TileNew(settings.tile_new_time, 0.0),
+            status: TileState::TileNew(Animation::new(now, settings.tile_new_time, settings.tile_new_easing)),
 
             settings: settings,
         }
     }
 
-    pub fn new_combined(settings: &'a Settings, score: i32, tile_x: i32, tile_y: i32) -> Tile<'a> {
+    pub fn new_static(settings: &'a Settings, score: i32, tile_x: i32, tile_y: i32) -> Tile<'a> {
+        Tile {
+            score,
+            tile_x,
+            tile_y,
+            status: TileState::TileStatic,
+
+            settings,
+        }
+    }
+
+    /// A tile which two others combined into at `now`.
+    pub fn new_combined(settings: &'a Settings, score: i32, tile_x: i32, tile_y: i32, now: f64) -> Tile<'a> {
         Tile {
             score: score,
             tile_x: tile_x,
             tile_y: tile_y,
-            status: TileState::TileCombine(settings.tile_combine_time, 1.2 * settings.tile_size),
+            status: TileState::TileCombine(Animation::new(now, settings.tile_combine_time, settings.tile_combine_easing)),
 
             settings: settings,
         }
@@ -53,67 +71,52 @@ impl<'a> Tile<'a> {
         (x, y)
     }
 
-    pub fn start_moving(&mut self, destination_tile_x: i32, destination_tile_y: i32) {
-        match self.status {
-            TileState::TileMoving(_, _, _, ox, oy) => {
-                let (x, y) = self.tile_to_pos(ox, oy);
-                self.status = TileState::TileMoving(self.settings.tile_move_time, x, y, ox, oy);
-                self.tile_x = destination_tile_x;
-                self.tile_y = destination_tile_y;
-            },
-            TileState::TileStatic => {
-                let (x, y) = self.tile_to_pos(self.tile_x, self.tile_y);
-                self.status = TileState::TileMoving(self.settings.tile_move_time, x, y, self.tile_x, self.tile_y);
-                self.tile_x = destination_tile_x;
-                self.tile_y = destination_tile_y;
-            },
-            _ => {},
-        }
+    /// Slides the tile to another cell from `now`, starting over from where
+    /// it came from if it was moving already.
+    pub fn start_moving(&mut self, destination_tile_x: i32, destination_tile_y: i32, now: f64) {
+        let (ox, oy) = match self.status {
+            TileState::TileMoving(_, ox, oy) => (ox, oy),
+            TileState::TileStatic => (self.tile_x, self.tile_y),
+            _ => return,
+        };
+
+        let animation = Animation::new(now, self.settings.tile_move_time, self.settings.tile_move_easing);
+        self.status = TileState::TileMoving(animation, ox, oy);
+        self.tile_x = destination_tile_x;
+        self.tile_y = destination_tile_y;
     }
 
-    pub fn update(&mut self, dt: f64) {
-        match self.status {
-            TileState::TileMoving(t, x, y, ox, oy) => {
-                if dt >= t {
-                    self.status = TileState::TileStatic;
-                } else {
-                    let (dx, dy) = self.tile_to_pos(self.tile_x, self.tile_y);
-                    let factor = dt / t;
-                    self.status = TileState::TileMoving(t - dt, x + factor * (dx - x), y + factor * (dy - y), ox, oy);
-                }
-            },
-            TileState::TileNew(t, size) => {
-                if dt >= t {
-                    self.status = TileState::TileStatic;
-                } else {
-                    let factor = dt / t;
-                    self.status = TileState::TileNew(t - dt, size + factor * (self.settings.tile_size - size));
-                }
-            },
-            TileState::TileCombine(t, size) => {
-                if dt >= t {
-                    self.status = TileState::TileStatic;
-                } else {
-                    let factor = dt / t;
-                    self.status = TileState::TileCombine(t - dt, size + factor * (self.settings.tile_size - size));
-                }
-            },
-            _ => {},
+    /// Ends the animation of the tile once it is over at `now`.
+    pub fn update(&mut self, now: f64) {
+        let finished = match self.status {
+            TileState::TileMoving(ref animation, _, _)
+            | TileState::TileNew(ref animation)
+            | TileState::TileCombine(ref animation) => animation.is_finished(now),
+            TileState::TileStatic => false,
+        };
+
+        if finished {
+            self.status = TileState::TileStatic;
         }
     }
 
-    pub fn render(&self, number_renderer: &NumberRenderer, c: &Context, gl: &mut GlGraphics) {
+    /// Draws the tile as it is at `now`.
+    pub fn render(&self, theme: &Theme, number_renderer: &NumberRenderer, now: f64, c: &Context, gl: &mut GlGraphics) {
+        let tile_size = self.settings.tile_size;
         let mut pos = self.tile_to_pos(self.tile_x, self.tile_y);
-        let mut size = (self.settings.tile_size, self.settings.tile_size);
+        let mut size = (tile_size, tile_size);
 
         match self.status {
-            TileState::TileMoving(_, x, y, _, _) => {
-                pos = (x, y);
+            TileState::TileMoving(ref animation, ox, oy) => {
+                let (x, y) = self.tile_to_pos(ox, oy);
+                pos = (animation.interpolate(x, pos.0, now), animation.interpolate(y, pos.1, now));
             },
-            TileState::TileNew(_, s) => {
+            TileState::TileNew(ref animation) => {
+                let s = animation.interpolate(0.0, tile_size, now);
                 size = (s, s);
             },
-            TileState::TileCombine(_, s) => {
+            TileState::TileCombine(ref animation) => {
+                let s = animation.interpolate(COMBINE_SCALE * tile_size, tile_size, now);
                 size = (s, s);
             },
             _ => {},
@@ -121,7 +124,7 @@ impl<'a> Tile<'a> {
 
         let (x, y) = pos;
         let (w, h) = size;
-        let color = self.get_color();
+        let color = theme.tile_color(self.score);
 
         Rectangle::new([color[0], color[1], color[2], 1.0])
             .draw(rectangle::centered([x + self.settings.tile_size / 2.0,
@@ -131,21 +134,8 @@ impl<'a> Tile<'a> {
                   c.transform,
                   gl);
 
-        let color = if self.score >= 8 {
-            self.settings.text_light_color
-        } else {
-            self.settings.text_dark_color
-        };
+        let color = theme.text_color(self.score);
 
         number_renderer.render(self.score as u32, x + self.settings.tile_size / 2.0, y + self.settings.tile_size / 2.0, self.settings.tile_size, color, c, gl);
     }
-
-    fn get_color(&self) -> [f32; 3] {
-        let i = (self.score as f64).log2() as usize;
-        if i > 0 && i < self.settings.tiles_colors.len() {
-            self.settings.tiles_colors[i]
-        } else {
-            self.settings.tile_unknow_color
-        }
-    }
 }
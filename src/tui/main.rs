@@ -0,0 +1,79 @@
+//! Plays the game in a terminal, for when there is no window to open.
+
+extern crate rand;
+extern crate termion;
+extern crate rust_2048;
+
+use std::io;
+use std::path::Path;
+use rand::random;
+use termion::event::Key;
+use termion::input::TermRead;
+use termion::raw::IntoRawMode;
+use termion::screen::AlternateScreen;
+use rust_2048::game::{ Direction, Game };
+use rust_2048::protocol;
+use rust_2048::settings::Settings;
+use rust_2048::theme::Theme;
+
+mod view;
+
+fn has_flag(name: &str) -> bool {
+    std::env::args().skip(1).any(|arg| arg == name)
+}
+
+/// Arrow keys, WASD and hjkl all move the tiles.
+fn direction(key: Key) -> Option<Direction> {
+    match key {
+        Key::Up | Key::Char('w') | Key::Char('k') => Some(Direction::Up),
+        Key::Down | Key::Char('s') | Key::Char('j') => Some(Direction::Down),
+        Key::Left | Key::Char('a') | Key::Char('h') => Some(Direction::Left),
+        Key::Right | Key::Char('d') | Key::Char('l') => Some(Direction::Right),
+        _ => None,
+    }
+}
+
+fn new_game(settings: &Settings) -> Game {
+    Game::new(settings.rules(), settings.seed.unwrap_or_else(random))
+}
+
+fn main() {
+    let mut settings = Settings::load();
+    settings.read_seed_arg(std::env::args().skip(1));
+
+    if has_flag("--protocol") {
+        let stdin = io::stdin();
+        protocol::run(settings.rules(), settings.seed, stdin.lock(), &mut io::stdout()).unwrap();
+        return;
+    }
+
+    let mut game = new_game(&settings);
+    let themes = Theme::load_all(&Path::new(&settings.asset_folder).join("themes"));
+    let mut theme = Theme::find(&themes, &settings.theme);
+
+    let stdin = io::stdin();
+    let stdout = io::stdout().into_raw_mode().unwrap_or_else(|e| { panic!("Failed to set up the terminal: {}", e) });
+    let mut screen = AlternateScreen::from(stdout);
+
+    view::render(&mut screen, &themes[theme], &game).unwrap();
+
+    for key in stdin.keys() {
+        match key.unwrap() {
+            Key::Char('q') | Key::Esc | Key::Ctrl('c') => break,
+            Key::Char('z') => game.undo(),
+            Key::Char('y') => game.redo(),
+            Key::Char(' ') => game = new_game(&settings),
+            Key::Char('\n') => game.keep_playing(),
+            Key::Char('t') => theme = (theme + 1) % themes.len(),
+            key => {
+                if let Some(direction) = direction(key) {
+                    game.move_tiles(direction);
+                }
+            },
+        }
+
+        view::render(&mut screen, &themes[theme], &game).unwrap();
+    }
+
+    view::restore(&mut screen).unwrap();
+}
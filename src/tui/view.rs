@@ -0,0 +1,63 @@
+//! Draws the board with colored cells, in the colors of the theme.
+
+use std::io::{ self, Write };
+use termion::{ clear, color, cursor };
+use rust_2048::game::{ Game, Status };
+use rust_2048::theme::Theme;
+
+/// Size of a cell in characters, wide enough for six digits.
+static CELL_WIDTH: usize = 8;
+static CELL_HEIGHT: usize = 3;
+
+fn rgb(c: [f32; 3]) -> color::Rgb {
+    color::Rgb((c[0] * 255.0) as u8, (c[1] * 255.0) as u8, (c[2] * 255.0) as u8)
+}
+
+pub fn render<W: Write>(out: &mut W, theme: &Theme, game: &Game) -> io::Result<()> {
+    // the terminal is in raw mode, so lines end with "\r\n"
+    write!(out, "{}{}{}", clear::All, cursor::Hide, cursor::Goto(1, 1))?;
+    write!(out, "Score: {}    Seed: {}\r\n\r\n", game.score(), game.seed())?;
+
+    let board = color::Bg(rgb(theme.label_color));
+    let reset = format!("{}{}", color::Bg(color::Reset), color::Fg(color::Reset));
+    let gap_line = " ".repeat(game.width() as usize * (CELL_WIDTH + 1) + 1);
+
+    write!(out, "{}{}{}\r\n", board, gap_line, reset)?;
+    for y in 0..game.height() {
+        for line in 0..CELL_HEIGHT {
+            for x in 0..game.width() {
+                let score = game.get_tile(x, y).map(|tile| tile.score).unwrap_or(0);
+                let text = if score > 0 && line == CELL_HEIGHT / 2 {
+                    score.to_string()
+                } else {
+                    String::new()
+                };
+
+                write!(out, "{} {}{}{:^width$}",
+                       board,
+                       color::Bg(rgb(theme.tile_color(score))),
+                       color::Fg(rgb(theme.text_color(score))),
+                       text,
+                       width = CELL_WIDTH)?;
+            }
+            write!(out, "{} {}\r\n", board, reset)?;
+        }
+        write!(out, "{}{}{}\r\n", board, gap_line, reset)?;
+    }
+
+    let message = match game.status() {
+        Status::Won => "You win! Press Enter to keep going or Space to restart.",
+        Status::GameOver => "Game over! Press Space to try again.",
+        _ => "",
+    };
+    write!(out, "\r\n{}\r\n", message)?;
+    write!(out, "Arrows/WASD/hjkl: move    Z/Y: undo/redo    Space: restart    T: theme    Q: quit\r\n")?;
+
+    out.flush()
+}
+
+/// Shows the cursor again before leaving.
+pub fn restore<W: Write>(out: &mut W) -> io::Result<()> {
+    write!(out, "{}", cursor::Show)?;
+    out.flush()
+}